@@ -12,11 +12,72 @@
 // If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
 //
 
+use crate::ct;
 use std::{fmt, ops, str};
 
 /// Character set in lexicographic order
 pub const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
 
+/// The reduction polynomial x^5 + x^3 + 1, with the x^5 term included
+pub(crate) const REDUCTION: u8 = 0x29;
+
+/// Multiplies a GF(32) element by x, reducing by the given polynomial
+const fn mul_alpha_reduce(x: u8, reduction: u8) -> u8 {
+    let x = x << 1;
+    if x & 0x20 == 0x20 {
+        x ^ reduction
+    } else {
+        x
+    }
+}
+
+/// Table of powers of alpha, doubled up so that the sum of two logarithms
+/// can be looked up without reducing it mod 31
+pub(crate) const fn exp_table(reduction: u8) -> [u8; 62] {
+    let mut ret = [0; 62];
+    let mut acc = 1;
+    let mut i = 0;
+    while i < 62 {
+        ret[i] = acc;
+        acc = mul_alpha_reduce(acc, reduction);
+        i += 1;
+    }
+    ret
+}
+
+/// Table of discrete logarithms base alpha. The entry for 0 is meaningless.
+pub(crate) const fn log_table(reduction: u8) -> [u8; 32] {
+    let exp = exp_table(reduction);
+    let mut ret = [0; 32];
+    let mut i = 0;
+    while i < 31 {
+        ret[exp[i] as usize] = i as u8;
+        i += 1;
+    }
+    ret
+}
+
+/// Full 32x32 multiplication table
+pub(crate) const fn mul_table(reduction: u8) -> [[u8; 32]; 32] {
+    let exp = exp_table(reduction);
+    let log = log_table(reduction);
+    let mut ret = [[0; 32]; 32];
+    let mut a = 1;
+    while a < 32 {
+        let mut b = 1;
+        while b < 32 {
+            ret[a][b] = exp[log[a] as usize + log[b] as usize];
+            b += 1;
+        }
+        a += 1;
+    }
+    ret
+}
+
+const EXP: [u8; 62] = exp_table(REDUCTION);
+const LOG: [u8; 32] = log_table(REDUCTION);
+const MUL: [[u8; 32]; 32] = mul_table(REDUCTION);
+
 /// An element of GF(32) constructed as GF(2)[x] mod x^5 + x^3 + 1
 ///
 /// Elements are represented using the bech32 alphabet
@@ -25,13 +86,120 @@ pub const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
 pub struct u5(u8);
 
 impl u5 {
+    /// The additive identity of the field
+    pub const ZERO: u5 = u5(0);
+    /// The multiplicative identity of the field
+    pub const ONE: u5 = u5(1);
+    /// The primitive element x of GF(2)[x] / (x^5 + x^3 + 1)
+    ///
+    /// Since the multiplicative group of GF(32) has prime order 31, every
+    /// element other than 0 and 1 is primitive; this is simply the one that
+    /// corresponds to the polynomial x.
+    pub const ALPHA: u5 = u5(2);
+
     fn mul_alpha(&mut self) {
-        self.0 <<= 1;
-        if self.0 & 0x20 == 0x20 {
-            self.0 ^= 0x29;
+        self.0 = mul_alpha_reduce(self.0, REDUCTION);
+    }
+
+    /// Multiplies two elements by shifting and adding
+    ///
+    /// This is the "textbook" algorithm, which does five conditional additions
+    /// and four multiplications by alpha. It is retained as a reference for
+    /// the table-driven multiplication used by the `Mul` impl.
+    pub fn mul_shift_add(self, mut other: u5) -> u5 {
+        let mut res: u5 = u5(0);
+        if self.0 & 0x01 != 0 {
+            res += other;
+        }
+        other.mul_alpha();
+        if self.0 & 0x02 != 0 {
+            res += other;
+        }
+        other.mul_alpha();
+        if self.0 & 0x04 != 0 {
+            res += other;
+        }
+        other.mul_alpha();
+        if self.0 & 0x08 != 0 {
+            res += other;
+        }
+        other.mul_alpha();
+        if self.0 & 0x10 != 0 {
+            res += other;
+        }
+        res
+    }
+
+    /// Multiplies two elements by looking them up in a precomputed table
+    ///
+    /// The 32x32 table is generated at compile time from the reduction polynomial.
+    pub fn mul_table(self, other: u5) -> u5 {
+        u5(MUL[usize::from(self.0)][usize::from(other.0)])
+    }
+
+    /// Multiplies two elements in constant time
+    ///
+    /// Unlike the `Mul` impl this has no branches or table lookups which
+    /// depend on the inputs, so it is suitable for use on secret data.
+    pub fn ct_mul(self, other: u5) -> u5 {
+        u5(ct::mul(u32::from(self.0), u32::from(other.0)) as u8)
+    }
+
+    /// Computes alpha^n, where alpha is the primitive element `u5::ALPHA`
+    ///
+    /// Since alpha has order 31, the exponent is reduced mod 31.
+    pub fn alpha_pow(n: usize) -> u5 {
+        u5(EXP[n % 31])
+    }
+
+    /// Computes the discrete logarithm of the element with respect to `u5::ALPHA`
+    ///
+    /// Returns a number in the range [0, 30], or `None` if the element is zero.
+    pub fn log_alpha(self) -> Option<usize> {
+        if self == u5::ZERO {
+            None
+        } else {
+            Some(LOG[usize::from(self.0)].into())
         }
     }
 
+    /// Computes the multiplicative inverse of the element, or `None` if it is zero
+    pub fn inverse(self) -> Option<u5> {
+        self.log_alpha().map(|n| u5::alpha_pow(31 - n))
+    }
+
+    /// Raises the element to the `n`th power
+    ///
+    /// By convention 0^0 is 1.
+    pub fn pow(self, n: usize) -> u5 {
+        match self.log_alpha() {
+            Some(log) => u5::alpha_pow((log * (n % 31)) % 31),
+            None if n == 0 => u5::ONE,
+            None => u5::ZERO,
+        }
+    }
+
+    /// Construct a u5 from an ASCII character at compile time
+    ///
+    /// Panics (and therefore fails to compile, if evaluated in a constant) if
+    /// the character is not in the bech32 alphabet.
+    pub const fn from_char_const(c: u8) -> u5 {
+        let c = c.to_ascii_lowercase();
+        let mut i = 0;
+        while i < CHARSET.len() {
+            if CHARSET[i] == c {
+                return u5(i as u8);
+            }
+            i += 1;
+        }
+        panic!("invalid bech32 character");
+    }
+
+    /// The 5-bit encoding of the element, usable at compile time
+    pub const fn to_u8(self) -> u8 {
+        self.0
+    }
+
     /// Construct a u5 from a character
     pub fn from_char(c: char) -> Result<Self, String> {
         match c {
@@ -70,6 +238,20 @@ impl u5 {
             x => Err(format!("invalid bech32 character {}", x)),
         }
     }
+
+    /// Construct a u5 from a character, in constant time
+    ///
+    /// This compares the character against every entry of the character set,
+    /// rather than branching on it. Only the validity of the character is
+    /// revealed, through the returned `Result`.
+    pub fn ct_from_char(c: char) -> Result<Self, String> {
+        let (value, found) = ct::from_char(u32::from(c));
+        if found != 0 {
+            Ok(u5(value as u8))
+        } else {
+            Err("invalid bech32 character".into())
+        }
+    }
 }
 
 impl fmt::Display for u5 {
@@ -116,28 +298,8 @@ impl ops::AddAssign<u5> for u5 {
 
 impl ops::Mul for u5 {
     type Output = u5;
-    fn mul(self, mut other: u5) -> u5 {
-        let mut res: u5 = u5(0);
-        if self.0 & 0x01 != 0 {
-            res += other;
-        }
-        other.mul_alpha();
-        if self.0 & 0x02 != 0 {
-            res += other;
-        }
-        other.mul_alpha();
-        if self.0 & 0x04 != 0 {
-            res += other;
-        }
-        other.mul_alpha();
-        if self.0 & 0x08 != 0 {
-            res += other;
-        }
-        other.mul_alpha();
-        if self.0 & 0x10 != 0 {
-            res += other;
-        }
-        res
+    fn mul(self, other: u5) -> u5 {
+        self.mul_table(other)
     }
 }
 impl ops::MulAssign<u5> for u5 {
@@ -146,6 +308,37 @@ impl ops::MulAssign<u5> for u5 {
     }
 }
 
+impl ops::Div for u5 {
+    type Output = u5;
+    fn div(self, other: u5) -> u5 {
+        match other.inverse() {
+            Some(inv) => self * inv,
+            None => panic!("Tried to divide {:?} by zero", self),
+        }
+    }
+}
+impl ops::DivAssign<u5> for u5 {
+    fn div_assign(&mut self, other: u5) {
+        *self = *self / other;
+    }
+}
+
+/// Parses a string of exactly `N` bech32 characters into an array at compile time
+///
+/// Panics (and therefore fails to compile, if evaluated in a constant) if the
+/// string has the wrong length or contains characters outside the alphabet.
+pub const fn u5_array<const N: usize>(s: &str) -> [u5; N] {
+    let bytes = s.as_bytes();
+    assert!(bytes.len() == N, "string has the wrong length");
+    let mut ret = [u5(0); N];
+    let mut i = 0;
+    while i < N {
+        ret[i] = u5::from_char_const(bytes[i]);
+        i += 1;
+    }
+    ret
+}
+
 /// A GF(32) "bech32" string
 #[allow(non_camel_case_types)]
 #[derive(Clone, Default, PartialEq, Eq, Hash)]
@@ -195,6 +388,13 @@ impl u5String {
         self.0.iter().all(|ch| *ch == u5(0))
     }
 
+    /// Return whether or not this string contains only 0 characters, in
+    /// constant time (for a given string length)
+    pub fn ct_is_all_zero(&self) -> bool {
+        let words: Vec<u32> = self.0.iter().map(|ch| u32::from(ch.0)).collect();
+        ct::all_zero(&words) != 0
+    }
+
     /// Converts the string to a bytestring
     ///
     /// If there are not a multiple-of-8 number of bits, pad on the right (LSB
@@ -259,7 +459,7 @@ impl fmt::Debug for u5String {
 impl str::FromStr for u5String {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, String> {
-        let mut ret = Vec::with_capacity((s.len() * 8 + 4) / 5);
+        let mut ret = Vec::with_capacity((s.len() * 8).div_ceil(5));
         for ch in s.chars() {
             ret.push(u5::from_char(ch)?);
         }
@@ -285,3 +485,112 @@ where
         &mut self.0[idx]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_u5() -> impl Iterator<Item = u5> + Clone {
+        (0..32u8).map(u5::from)
+    }
+
+    #[test]
+    fn field_axioms() {
+        for a in all_u5() {
+            assert_eq!(a + u5::ZERO, a);
+            assert_eq!(a * u5::ONE, a);
+            assert_eq!(a * u5::ZERO, u5::ZERO);
+            assert_eq!(a + a, u5::ZERO);
+            for b in all_u5() {
+                assert_eq!(a + b, b + a);
+                assert_eq!(a * b, b * a);
+                for c in all_u5() {
+                    assert_eq!((a + b) + c, a + (b + c));
+                    assert_eq!((a * b) * c, a * (b * c));
+                    assert_eq!(a * (b + c), a * b + a * c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mul_table_matches_shift_add() {
+        for a in all_u5() {
+            for b in all_u5() {
+                assert_eq!(a.mul_table(b), a.mul_shift_add(b));
+            }
+        }
+    }
+
+    #[test]
+    fn const_parsing() {
+        const ARRAY: [u5; 6] = u5_array("QpZry9");
+        assert_eq!(ARRAY.map(u5::to_u8), [0, 1, 2, 3, 4, 5]);
+        for (i, &ch) in CHARSET.iter().enumerate() {
+            assert_eq!(u5::from_char_const(ch), u5::from_char(ch.into()).unwrap());
+            assert_eq!(u5::from_char_const(ch).to_u8(), i as u8);
+        }
+    }
+
+    #[test]
+    fn constant_time() {
+        for a in all_u5() {
+            for b in all_u5() {
+                assert_eq!(a.ct_mul(b), a * b);
+            }
+        }
+        for c in (0..0x80u8).map(char::from).chain(['ß', '€']) {
+            assert_eq!(u5::ct_from_char(c).ok(), u5::from_char(c).ok());
+        }
+        for s in ["", "q", "qqqq", "qqpq", "lqqq", "mstests"] {
+            let s: u5String = s.parse().unwrap();
+            assert_eq!(s.ct_is_all_zero(), s.is_all_zero());
+        }
+    }
+
+    #[test]
+    fn inverse_and_division() {
+        assert_eq!(u5::ZERO.inverse(), None);
+        for a in all_u5() {
+            if let Some(inv) = a.inverse() {
+                assert_eq!(a * inv, u5::ONE);
+                assert_eq!(inv.inverse(), Some(a));
+            } else {
+                assert_eq!(a, u5::ZERO);
+            }
+            for b in all_u5().filter(|&b| b != u5::ZERO) {
+                assert_eq!((a / b) * b, a);
+                let mut c = a;
+                c /= b;
+                assert_eq!(c, a / b);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn divide_by_zero() {
+        let _ = u5::ONE / u5::ZERO;
+    }
+
+    #[test]
+    fn log_and_pow() {
+        assert_eq!(u5::ZERO.log_alpha(), None);
+        assert_eq!(u5::ZERO.pow(0), u5::ONE);
+        assert_eq!(u5::ZERO.pow(5), u5::ZERO);
+        for n in 0..31 {
+            assert_eq!(u5::alpha_pow(n).log_alpha(), Some(n));
+            assert_eq!(u5::alpha_pow(n + 31), u5::alpha_pow(n));
+        }
+        for a in all_u5() {
+            if let Some(log) = a.log_alpha() {
+                assert_eq!(u5::alpha_pow(log), a);
+            }
+            let mut acc = u5::ONE;
+            for n in 0..64 {
+                assert_eq!(a.pow(n), acc);
+                acc *= a;
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// BCH Structure
+///
+/// All of our checksums are BCH codes: their generators are the products of
+/// the minimal polynomials of a run of consecutive powers r, rβ, ..., rβ^{δ-2}
+/// of some element β of an extension field. By the BCH bound, the resulting
+/// code has minimum distance at least δ (the "designed distance") as long as
+/// the codewords are no longer than the multiplicative order of β.
+///
+/// This module recovers that structure from a generator, so that we can check
+/// the properties of a new checksum rather than taking them on faith.
+///
+use crate::base32::u5;
+use crate::extfield::{ExtElem, ExtField, MAX_DEGREE};
+use crate::field::Field;
+use crate::poly::Poly;
+use std::fmt;
+
+/// The BCH structure of a checksum generator
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BchStructure {
+    /// The generator itself
+    pub modulus: Poly<u5>,
+    /// The monic irreducible factors of the generator over GF(32), sorted by degree
+    pub factors: Vec<Poly<u5>>,
+    /// The smallest extension field containing all the roots of the generator
+    pub field: ExtField,
+    /// The roots of the generator
+    pub roots: Vec<ExtElem>,
+    /// The first root r in the longest run r, rβ, rβ^2, ... of roots
+    pub first_root: ExtElem,
+    /// The ratio β between consecutive roots in the run
+    pub beta: ExtElem,
+    /// The designed distance δ, which is one more than the length of the run
+    pub designed_distance: usize,
+    /// The maximum length of a codeword for which the BCH bound holds, i.e.
+    /// the multiplicative order of β
+    pub length: u64,
+}
+
+impl BchStructure {
+    /// Determines the BCH structure of a generator polynomial
+    pub fn analyze(modulus: &Poly<u5>) -> Result<BchStructure, String> {
+        let degree = match modulus.degree() {
+            Some(0) | None => return Err("generator must have positive degree".into()),
+            Some(d) => d,
+        };
+        if modulus.leading_coefficient() != u5::ONE {
+            return Err(format!("generator {modulus:?} is not monic"));
+        }
+        if modulus.coefficient(0) == u5::ZERO {
+            return Err(format!("generator {modulus:?} is divisible by x"));
+        }
+        if modulus.gcd(&modulus.derivative()) != Poly::one() {
+            return Err(format!("generator {modulus:?} has repeated factors"));
+        }
+
+        // For a squarefree polynomial, the degree of its splitting field is
+        // the smallest k such that it divides x^(32^k) - x
+        let x = Poly::x().div_rem(modulus).1;
+        let mut xq = x.clone();
+        let mut split_degree = None;
+        for k in 1..=MAX_DEGREE {
+            xq = xq.pow_mod(32, modulus);
+            if xq == x {
+                split_degree = Some(k);
+                break;
+            }
+        }
+        let field = match split_degree {
+            Some(k) => ExtField::with_degree(k),
+            None => {
+                return Err(format!(
+                    "generator {modulus:?} does not split in any extension of degree at most {MAX_DEGREE}"
+                ))
+            }
+        };
+        let roots = field.roots(modulus);
+        assert_eq!(
+            roots.len(),
+            degree,
+            "squarefree polynomial splits into distinct roots"
+        );
+
+        // Group the roots into conjugacy classes to obtain the factors
+        let mut factors = vec![];
+        let mut seen = vec![];
+        for &root in &roots {
+            if !seen.contains(&root) {
+                seen.extend(root.conjugates());
+                factors.push(root.minimal_polynomial());
+            }
+        }
+        factors.sort_by_key(|f| (f.degree(), f.to_le_u5string(degree + 1).to_string()));
+
+        // Find the longest run of roots r, rβ, rβ^2, ..., preferring the
+        // largest order of β in case of ties. A single root is a run of length
+        // 1 with β = 1, which gives the (trivial) designed distance 2.
+        let mut first_root = roots[0];
+        let mut beta = ExtElem::ONE;
+        let mut run_len = 1;
+        let mut length = 1;
+        for &r in &roots {
+            for &s in roots.iter().filter(|&&s| s != r) {
+                let b = s / r;
+                let mut run = 2;
+                let mut next = s * b;
+                while next != r && roots.contains(&next) {
+                    run += 1;
+                    next *= b;
+                }
+                let order = b.multiplicative_order().unwrap();
+                if (run, order) > (run_len, length) {
+                    first_root = r;
+                    beta = b;
+                    run_len = run;
+                    length = order;
+                }
+            }
+        }
+
+        Ok(BchStructure {
+            modulus: modulus.clone(),
+            factors,
+            field,
+            roots,
+            first_root,
+            beta,
+            designed_distance: run_len + 1,
+            length,
+        })
+    }
+
+    /// The roots r, rβ, ..., rβ^{δ-2} which determine the designed distance
+    pub fn consecutive_roots(&self) -> Vec<ExtElem> {
+        let mut next = self.first_root;
+        (0..self.designed_distance - 1)
+            .map(|_| {
+                let ret = next;
+                next *= self.beta;
+                ret
+            })
+            .collect()
+    }
+
+    /// The number of substitution errors which are guaranteed to be detected,
+    /// for strings of length at most `self.length`
+    pub fn detectable_errors(&self) -> usize {
+        self.designed_distance - 1
+    }
+
+    /// The number of substitution errors which are guaranteed to be correctable,
+    /// for strings of length at most `self.length`
+    pub fn correctable_errors(&self) -> usize {
+        (self.designed_distance - 1) / 2
+    }
+}
+
+impl fmt::Display for BchStructure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let degree = self.roots.len();
+        writeln!(
+            f,
+            "generator:          {}",
+            self.modulus.to_le_u5string(degree + 1)
+        )?;
+        write!(f, "factors:           ")?;
+        for factor in &self.factors {
+            let len = factor.degree().unwrap() + 1;
+            write!(f, " {}", factor.to_le_u5string(len))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "splitting field:    {:?}", self.field)?;
+        write!(f, "roots:             ")?;
+        for root in &self.roots {
+            write!(f, " {root}")?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "consecutive roots:  r*β^i for 0 <= i < {}, with r = {} and β = {} of order {}",
+            self.designed_distance - 1,
+            self.first_root,
+            self.beta,
+            self.length,
+        )?;
+        writeln!(f, "designed distance:  {}", self.designed_distance)?;
+        writeln!(f, "maximum length:     {}", self.length)?;
+        write!(
+            f,
+            "guarantees:         detects {} errors, corrects {} errors",
+            self.detectable_errors(),
+            self.correctable_errors(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base32::u5String;
+    use std::str::FromStr;
+
+    fn analyze(s: &str) -> BchStructure {
+        BchStructure::analyze(&Poly::from_le_u5string(&u5String::from_str(s).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn bech32() {
+        let bch = analyze("ja45kap");
+        assert_eq!(bch.field.degree(), 2);
+        assert_eq!(bch.factors.len(), 3);
+        assert!(bch.factors.iter().all(|f| f.degree() == Some(2)));
+        assert_eq!(bch.designed_distance, 4);
+        assert_eq!(bch.length, 1023);
+        assert_eq!(bch.detectable_errors(), 3);
+        assert_eq!(bch.correctable_errors(), 1);
+        for root in bch.consecutive_roots() {
+            assert_eq!(bch.modulus.evaluate_in(root), ExtElem::ZERO);
+        }
+    }
+
+    #[test]
+    fn codex32() {
+        let bch = analyze("sscmleeeqg3mep");
+        assert_eq!(bch.field.degree(), 2);
+        let degrees: Vec<_> = bch.factors.iter().map(|f| f.degree().unwrap()).collect();
+        assert_eq!(degrees, vec![1, 1, 1, 2, 2, 2, 2, 2]);
+        assert_eq!(bch.designed_distance, 9);
+        assert_eq!(bch.length, 93);
+        assert_eq!(bch.correctable_errors(), 4);
+
+        let product = bch.factors.iter().fold(Poly::one(), |acc, f| &acc * f);
+        assert_eq!(product, bch.modulus);
+    }
+
+    #[test]
+    fn long_codex32() {
+        let bch = analyze("hyk9x4hx4ef6e20p");
+        assert_eq!(bch.field.degree(), 2);
+        assert_eq!(bch.designed_distance, 9);
+        assert_eq!(bch.length, 1023);
+        assert_eq!(bch.consecutive_roots().len(), 8);
+    }
+
+    #[test]
+    fn bad_generators() {
+        let parse = |s| Poly::from_le_u5string(&u5String::from_str(s).unwrap());
+        assert!(BchStructure::analyze(&parse("p")).is_err());
+        assert!(BchStructure::analyze(&parse("ja45kaz")).is_err()); // not monic
+        assert!(BchStructure::analyze(&parse("qa45kap")).is_err()); // divisible by x
+        assert!(BchStructure::analyze(&parse("9qpqp")).is_err()); // square
+
+        // A cubic only splits in GF(32^3)
+        let cubic = ExtField::with_degree(3).modulus();
+        let bch = BchStructure::analyze(&(&cubic * &parse("rpp"))).unwrap();
+        assert_eq!(bch.field.degree(), 6);
+        assert_eq!(bch.roots.len(), 5);
+    }
+}
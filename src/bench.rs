@@ -0,0 +1,97 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Benchmarks
+///
+/// Simple timing harness comparing the different GF(32) multiplication
+/// strategies when used to compute checksums. This is meant to be run from
+/// the command line with a release build, e.g.
+///
+/// ```text
+/// cargo run --release -- bench codex32 100000
+/// ```
+///
+use crate::base32::{u5, u5String};
+use crate::checksum32::Checksum;
+use crate::distance::Rng;
+use crate::packed::PackedChecksum;
+use std::{hint, time};
+
+/// Length, in characters, of the random strings which are checksummed
+pub const BENCH_STRING_LEN: usize = 128;
+
+/// Generates a deterministic batch of pseudorandom strings
+fn random_strings(n_strings: usize) -> Vec<u5String> {
+    let mut rng = Rng::new(Rng::SEED);
+    (0..n_strings)
+        .map(|_| rng.u5_string(BENCH_STRING_LEN))
+        .collect()
+}
+
+/// Runs `polymod` over every string in the batch with the given multiplication
+/// algorithm, returning the elapsed time
+fn time_polymod<M: Fn(u5, u5) -> u5 + Copy>(
+    checksum: &Checksum,
+    batch: &[u5String],
+    mul: M,
+) -> time::Duration {
+    let start = time::Instant::now();
+    for s in batch {
+        hint::black_box(checksum.polymod_with(hint::black_box(s), mul));
+    }
+    start.elapsed()
+}
+
+/// Benchmarks the shift-and-add and table-driven multiplication, as well as
+/// the packed checksum engine, on a batch of `n_strings` random strings,
+/// returning a human-readable report
+pub fn polymod(checksum: &Checksum, n_strings: usize) -> String {
+    let batch = random_strings(n_strings);
+    for s in &batch {
+        assert_eq!(
+            checksum.polymod_with(s, u5::mul_shift_add),
+            checksum.polymod_with(s, u5::mul_table),
+            "multiplication strategies disagree on {s}",
+        );
+    }
+
+    let shift_add = time_polymod(checksum, &batch, u5::mul_shift_add);
+    let table = time_polymod(checksum, &batch, u5::mul_table);
+    let per_string = |d: time::Duration| d.as_nanos() / n_strings.max(1) as u128;
+    let mut ret = format!(
+        "{n_strings} strings of {BENCH_STRING_LEN} characters\n\
+         shift-and-add: {:>12?} ({} ns/string)\n\
+         table:         {:>12?} ({} ns/string, {:.2}x)",
+        shift_add,
+        per_string(shift_add),
+        table,
+        per_string(table),
+        shift_add.as_secs_f64() / table.as_secs_f64(),
+    );
+
+    if let Ok(packed) = PackedChecksum::new(checksum) {
+        let start = time::Instant::now();
+        for s in &batch {
+            hint::black_box(packed.polymod(&hint::black_box(s)[..]));
+        }
+        let elapsed = start.elapsed();
+        ret.push_str(&format!(
+            "\npacked:        {:>12?} ({} ns/string, {:.2}x)",
+            elapsed,
+            per_string(elapsed),
+            shift_add.as_secs_f64() / elapsed.as_secs_f64(),
+        ));
+    }
+    ret
+}
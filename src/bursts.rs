@@ -0,0 +1,238 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Burst and Transposition Detection
+///
+/// Human transcription errors are often adjacent swaps or short bursts,
+/// rather than independent substitutions. Here we check, at every position of
+/// a string, every burst of each length up to some limit, and every adjacent
+/// (`ab` -> `ba`) and near (`abc` -> `cba`) transposition.
+///
+/// There are far too many bursts to try one by one, but the undetected ones
+/// are exactly the codewords supported on the burst's window, which form a
+/// vector space: the kernel of the parity-check columns of the window. So we
+/// count them by inclusion-exclusion on the kernels of the window with and
+/// without its first and last positions, which a burst must both change.
+///
+/// A transposition of `a` and `b` adds `a + b` at both positions, so it is
+/// detected exactly when the two positions have different parity-check
+/// columns, whatever the characters involved.
+///
+use std::fmt;
+
+use crate::base32::u5;
+use crate::checksum32::Checksum;
+use crate::linalg::Matrix;
+use crate::poly::Poly;
+
+/// The detection of one class of errors, over every position in a string
+#[derive(Clone, PartialEq, Debug)]
+pub struct Detection {
+    /// A description of the class of errors
+    pub errors: String,
+    /// The number of positions at which the class fits
+    pub positions: usize,
+    /// The number of positions at which some error of the class is undetected
+    pub bad_positions: usize,
+    /// The fraction of all errors of the class, over all positions, which
+    /// are undetected
+    pub undetected: f64,
+}
+
+impl Detection {
+    /// Whether every error of the class is detected, at every position
+    pub fn always_detected(&self) -> bool {
+        self.bad_positions == 0
+    }
+}
+
+impl fmt::Display for Detection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:26}  ", self.errors)?;
+        if self.always_detected() {
+            f.write_str("always detected")
+        } else {
+            write!(
+                f,
+                "{:.3e} undetected, at {} of {} positions",
+                self.undetected, self.bad_positions, self.positions
+            )
+        }
+    }
+}
+
+/// The parity-check columns of a checksum: column `j` is `x^j` modulo the
+/// generator, with position 0 at the end of the string
+fn columns(checksum: &Checksum, length: usize) -> Vec<Vec<u5>> {
+    let generator = checksum.generator();
+    let n = checksum.checksum_len();
+    let mut column = Poly::one();
+    (0..length)
+        .map(|_| {
+            let ret = (0..n).map(|i| column.coefficient(i)).collect();
+            column = (&column * &Poly::x()).div_rem(&generator).1;
+            ret
+        })
+        .collect()
+}
+
+/// The dimension of the space of codewords supported on some columns
+fn kernel_dim(n_rows: usize, columns: &[Vec<u5>]) -> i32 {
+    if columns.is_empty() {
+        0
+    } else {
+        (columns.len() - Matrix::from_columns(n_rows, columns).rank()) as i32
+    }
+}
+
+/// Checks every burst of length `burst_len` at every position of a string of
+/// `length` characters
+fn check_bursts(n_rows: usize, columns: &[Vec<u5>], burst_len: usize) -> Detection {
+    let positions = columns.len() + 1 - burst_len;
+    let mut bad_positions = 0;
+    let mut undetected = 0.0;
+    for window in columns.windows(burst_len) {
+        // The number of undetected bursts is 32^k4 * count
+        let (k4, count) = if burst_len == 1 {
+            (0, 32i64.pow(kernel_dim(n_rows, window) as u32) - 1)
+        } else {
+            let k1 = kernel_dim(n_rows, window);
+            let k2 = kernel_dim(n_rows, &window[1..]);
+            let k3 = kernel_dim(n_rows, &window[..burst_len - 1]);
+            let k4 = kernel_dim(n_rows, &window[1..burst_len - 1]);
+            let pow = |k: i32| 32i64.pow((k - k4) as u32);
+            (k4, pow(k1) - pow(k2) - pow(k3) + 1)
+        };
+        if count != 0 {
+            bad_positions += 1;
+            // Out of 31^2 * 32^(burst_len - 2) bursts, or 31 single errors
+            let total = if burst_len == 1 {
+                31.0
+            } else {
+                961.0 * 32f64.powi(burst_len as i32 - 2)
+            };
+            undetected += count as f64 * 32f64.powi(k4) / total;
+        }
+    }
+    Detection {
+        errors: format!("bursts of length {burst_len}"),
+        positions,
+        bad_positions,
+        undetected: undetected / positions as f64,
+    }
+}
+
+/// Checks every transposition of characters `gap` apart at every position
+fn check_transpositions(columns: &[Vec<u5>], gap: usize, name: &str) -> Detection {
+    let positions = columns.len().saturating_sub(gap);
+    let bad_positions = (0..positions)
+        .filter(|&j| columns[j] == columns[j + gap])
+        .count();
+    Detection {
+        errors: name.into(),
+        positions,
+        bad_positions,
+        undetected: bad_positions as f64 / positions.max(1) as f64,
+    }
+}
+
+/// Checks every burst of length up to `max_burst_len`, and every adjacent and
+/// near transposition, at every position of a string of `length` characters
+/// (including any expanded HRP)
+pub fn analyze(
+    checksum: &Checksum,
+    length: usize,
+    max_burst_len: usize,
+) -> Result<Vec<Detection>, String> {
+    if length < 3 {
+        return Err(format!("length {length} is too short to analyze"));
+    }
+    if max_burst_len == 0 || max_burst_len > length {
+        return Err(format!(
+            "burst length {max_burst_len} must be between 1 and {length}"
+        ));
+    }
+    let n = checksum.checksum_len();
+    let columns = columns(checksum, length);
+    let mut ret: Vec<_> = (1..=max_burst_len)
+        .map(|burst_len| check_bursts(n, &columns, burst_len))
+        .collect();
+    ret.push(check_transpositions(&columns, 1, "adjacent transpositions"));
+    ret.push(check_transpositions(&columns, 2, "near transpositions"));
+    Ok(ret)
+}
+
+/// Produces a table of the results of `analyze`
+pub fn report(checksum: &Checksum, length: usize, max_burst_len: usize) -> Result<String, String> {
+    let detections = analyze(checksum, length, max_burst_len)?;
+    let mut ret = format!("length {length}");
+    for detection in detections {
+        ret.push_str(&format!("\n    {detection}"));
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum32::get_checksums;
+    use crate::checksum32::tests as checksum_tests;
+
+    #[test]
+    fn parity() {
+        let parity = checksum_tests::parity();
+        let detections = analyze(&parity, 10, 3).unwrap();
+        assert!(detections[0].always_detected());
+        // Two changes cancel out when they are equal
+        assert_eq!(detections[1].bad_positions, 9);
+        assert!((detections[1].undetected - 1.0 / 31.0).abs() < 1e-12);
+        // Three cancel out when the middle is the sum of the other two
+        assert!((detections[2].undetected - 1.0 / 32.0).abs() < 1e-12);
+        // and transpositions never change the sum
+        assert_eq!(detections[3].errors, "adjacent transpositions");
+        assert_eq!(
+            (detections[3].positions, detections[3].bad_positions),
+            (9, 9)
+        );
+        assert_eq!(
+            (detections[4].positions, detections[4].bad_positions),
+            (8, 8)
+        );
+    }
+
+    #[test]
+    fn bech32() {
+        let checksums = get_checksums();
+        let detections = analyze(&checksums["bech32"], 50, 8).unwrap();
+        // Every burst no longer than the checksum is detected
+        for detection in &detections[..6] {
+            assert!(detection.always_detected(), "{detection}");
+        }
+        // and only multiples of the generator escape a burst of length 7
+        assert_eq!(detections[6].bad_positions, 44);
+        let expected = 31.0 / (961.0 * 32f64.powi(5));
+        assert!((detections[6].undetected - expected).abs() < expected * 1e-9);
+        assert!(!detections[7].always_detected());
+        assert!(detections[8].always_detected());
+        assert!(detections[9].always_detected());
+
+        let table = report(&checksums["codex32"], 48, 14).unwrap();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 17);
+        assert!(lines[13].ends_with("bursts of length 13         always detected"));
+        assert!(lines[14].contains("undetected, at 35 of 35 positions"));
+        assert!(lines[15].ends_with("always detected"));
+        assert!(analyze(&checksums["codex32"], 48, 49).is_err());
+    }
+}
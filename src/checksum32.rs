@@ -15,104 +15,659 @@
 /// Checksums
 ///
 /// This file defines the various checksums that we care about for the codex32
-/// project. This includes bech32 and bech32m (mostly for sanity checking),
+/// project. This includes bech32 and bech32m (mostly for sanity checking, with
+/// `bech32_variant` to tell which of the two a string was encoded with),
 /// codex32 itself, and "long codex32" which is the checksum used for strings
-/// longer than 93 characters.
+/// too long for codex32. Each checksum refuses strings which, counting their
+/// expanded HRP, are longer than its code, except that codex32 limits the data
+/// part to 93 characters as BIP-93 does; `select_codex32` chooses between the
+/// two codex32 checksums by the same rules.
 ///
 /// To define a new code, the process is roughly:
-///     1. Run Pieter's gen_bech.py code in his pychar repo. You may want to
-///        edit the final line that sets some default parameters.
-///     2. This will output a checksum with something like
-///        `gen=[23,4,22,5,6,21,23,6,21,25,9,26,25,10,15,1]`
-///     3. Obtain its "string" representation by copying any of the `get_mod_*`
-///        unit tests below and replacing the `genbch_str` variable.
-///     4. This is your `MODULUS_STRING`. For the `RESIDUE_STRING` just make something up.
-///     5. Modify the 'get_checksums()' function to add your new checksum.
+///     1. Run the `search` action with the checksum length, code length and
+///        target distance, e.g. `search 15,1023,9` (see the `search` module).
+///        This replaces Pieter's gen_bech.py code in his pychar repo.
+///     2. This will output generators in two forms, like
+///        `hyk9x4hx4ef6e20p  gen=[23,4,22,5,6,21,23,6,21,25,9,26,25,10,15,1]`
+///     3. The first is the "string" representation; the `gen=` form is what
+///        gen_bech.py outputs, and can be converted by copying any of the
+///        `get_mod_*` unit tests below and replacing the `genbch_str` variable.
+///     4. The string is your `MODULUS_STRING`. For the `RESIDUE_STRING` just make something up.
+///     5. Define a new type implementing `StaticChecksum` with these strings,
+///        and modify the 'get_checksums()' function to add your new checksum.
+///     6. Run the `analyze` action on it to confirm that it has the BCH
+///        structure (designed distance and length) that you expect.
+///
+/// Alternately, to experiment without recompiling, put the strings in a
+/// definitions file (see the `definitions` module) and pass it to the CLI with
+/// `--defs <file>`.
 ///
 /// To use the checksum with the codex32 PostScript code, replace the polymodulus
 /// variable with the `gen=` string, replacing the commas with spaces and IMPORTANTLY
 /// dropping the final 1, which is implicit in the Python code.
 ///
-use crate::base32::{u5, u5String};
-use std::{collections::HashMap, str::FromStr};
+use crate::base32::{self, u5, u5String};
+use crate::bch::BchStructure;
+use crate::ct;
+use crate::decode;
+use crate::extfield::ExtElem;
+use crate::gf32::Isomorphism;
+use crate::linalg::Matrix;
+use crate::listdecode;
+use crate::poly::Poly;
+use std::{collections::HashMap, fmt, str::FromStr, sync::OnceLock};
+
+/// The maximum number of decodings attempted by `Checksum::locate_errors`
+const LOCATE_WORK_LIMIT: usize = 10_000;
+
+/// Characters which may be used to mark erasures, i.e. characters of a string
+/// which are known to be unreadable
+pub const ERASURE_CHARS: [char; 2] = ['?', '_'];
 
 /// Returns the master list of checksums supported by this tool
-pub fn get_checksums() -> HashMap<&'static str, Checksum> {
+pub fn get_checksums() -> HashMap<String, Checksum> {
     vec![
-        ("bech32", Checksum::new("ja45kap", "qqqqqp")),
-        ("codex32", Checksum::new("sscmleeeqg3mep", "secretshare32")),
-        (
-            "long-codex32",
-            Checksum::new("hyk9x4hx4ef6e20p", "secretshare32ex"),
-        ),
+        (Bech32::NAME, Bech32::to_checksum()),
+        (Bech32m::NAME, Bech32m::to_checksum()),
+        (Codex32::NAME, Codex32::to_checksum()),
+        (LongCodex32::NAME, LongCodex32::to_checksum()),
     ]
     .into_iter()
+    .map(|(name, checksum)| (name.to_owned(), checksum))
     .collect()
 }
 
-/// Generic checksum trait
+/// Whether strings for a checksum must, may or must not have an HRP
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum HrpPolicy {
+    /// Strings must have a nonempty HRP, followed by the separator '1'
+    Required,
+    /// Strings may or may not have an HRP
+    #[default]
+    Optional,
+    /// Strings must not have an HRP, or a separator
+    Forbidden,
+}
+
+/// A string which has had erasures filled and/or errors corrected
 #[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Correction {
+    /// The corrected string
+    pub corrected: String,
+    /// The erasures which were filled, as (character index, old character,
+    /// new character), in order of index. The old and new characters may be
+    /// the same, if a character marked as erased turned out to be correct.
+    pub filled: Vec<(usize, char, char)>,
+    /// The (non-erased) errors which were corrected, as (character index, old
+    /// character, new character), in order of index
+    pub corrections: Vec<(usize, char, char)>,
+}
+
+/// A checksum whose parameters are chosen at runtime
+///
+/// See `StaticChecksum` for checksums whose parameters are fixed at compile
+/// time; the built-in ones are converted to this type for use by the CLI.
+#[derive(Clone, Debug)]
 pub struct Checksum {
     /// Stringified version of the modulus
     modulus: u5String,
     /// Stringified version of the
     residue: u5String,
+    /// Map from the field representation that the checksum is defined over
+    /// to our `u5` representation. The modulus and residue above have already
+    /// been mapped, but input strings must be mapped before processing.
+    representation: Isomorphism,
+    /// The maximum length of the data part of strings, if any
+    max_data_len: Option<usize>,
+    /// The maximum length of strings including the expanded HRP, i.e. of the
+    /// code, if any
+    max_code_len: Option<usize>,
+    /// Whether strings must, may or must not have an HRP
+    hrp_policy: HrpPolicy,
+    /// The BCH structure of the modulus, computed the first time it is needed
+    bch: OnceLock<Result<BchStructure, String>>,
+}
+
+impl PartialEq for Checksum {
+    fn eq(&self, other: &Checksum) -> bool {
+        // The BCH structure is determined by the other fields
+        self.modulus == other.modulus
+            && self.residue == other.residue
+            && self.representation == other.representation
+            && self.max_data_len == other.max_data_len
+            && self.max_code_len == other.max_code_len
+            && self.hrp_policy == other.hrp_policy
+    }
 }
+impl Eq for Checksum {}
 
 impl Checksum {
-    /// Construct a new checksum. This should only be called from the `get_checksums` function
-    /// in this file.
-    fn new(modulus_str: &str, residue_str: &str) -> Checksum {
-        assert!(
-            modulus_str.is_ascii(),
-            "Modulus string \"{modulus_str}\" must be ASCII"
-        );
-        assert!(
-            residue_str.is_ascii(),
-            "Residue string \"{residue_str}\" must be ASCII"
-        );
-        assert_eq!(
-            modulus_str.as_bytes()[modulus_str.len() - 1],
-            b'p',
-            "Modulus string \"{modulus_str}\" should end in 'p'.",
-        );
-        assert_eq!(modulus_str.len(), residue_str.len() + 1,);
+    /// Construct a new checksum over a different representation of GF(32)
+    ///
+    /// The characters of the modulus and residue strings, as well as of all
+    /// strings checksummed or validated by this checksum, are interpreted as
+    /// the 5-bit encodings of elements of the field that `representation`
+    /// maps from.
+    pub fn new_in_representation(
+        modulus_str: &str,
+        residue_str: &str,
+        representation: Isomorphism,
+    ) -> Checksum {
+        match Checksum::try_new_in_representation(modulus_str, residue_str, representation) {
+            Ok(checksum) => checksum,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Construct a new checksum over a different representation of GF(32),
+    /// returning an error rather than panicking if the strings are malformed
+    pub fn try_new_in_representation(
+        modulus_str: &str,
+        residue_str: &str,
+        representation: Isomorphism,
+    ) -> Result<Checksum, String> {
+        if !modulus_str.is_ascii() {
+            return Err(format!("Modulus string \"{modulus_str}\" must be ASCII"));
+        }
+        if !residue_str.is_ascii() {
+            return Err(format!("Residue string \"{residue_str}\" must be ASCII"));
+        }
+        if !modulus_str.ends_with('p') {
+            return Err(format!(
+                "Modulus string \"{modulus_str}\" should end in 'p'."
+            ));
+        }
+        if residue_str.is_empty() || modulus_str.len() != residue_str.len() + 1 {
+            return Err(format!(
+                "Residue string \"{residue_str}\" should be one character shorter than \
+                 modulus string \"{modulus_str}\", and nonempty."
+            ));
+        }
+
+        let modulus = u5String::from_str(modulus_str)
+            .map_err(|e| format!("Modulus string \"{modulus_str}\" was not a u5 string: {e}"))?;
+        let residue = u5String::from_str(residue_str)
+            .map_err(|e| format!("Residue string \"{residue_str}\" was not a u5 string: {e}"))?;
+        Ok(Checksum {
+            modulus: representation.string_to_u5(&modulus),
+            residue: representation.string_to_u5(&residue),
+            representation,
+            max_data_len: None,
+            max_code_len: None,
+            hrp_policy: HrpPolicy::Optional,
+            bch: OnceLock::new(),
+        })
+    }
+
+    /// Set the maximum length of the data part of strings (everything after
+    /// the separator, including the checksum), in characters
+    pub fn with_max_data_len(mut self, max_data_len: usize) -> Checksum {
+        self.max_data_len = Some(max_data_len);
+        self
+    }
+
+    /// Set the maximum length of strings including the expanded HRP (which is
+    /// twice the length of the HRP, plus one), in characters
+    ///
+    /// This is normally the length of the code, beyond which the guarantees
+    /// of its BCH structure do not hold.
+    pub fn with_max_code_len(mut self, max_code_len: usize) -> Checksum {
+        self.max_code_len = Some(max_code_len);
+        self
+    }
+
+    /// Set whether strings must, may or must not have an HRP
+    pub fn with_hrp_policy(mut self, hrp_policy: HrpPolicy) -> Checksum {
+        self.hrp_policy = hrp_policy;
+        self
+    }
+
+    /// The maximum length of the data part of strings, if any
+    pub fn max_data_len(&self) -> Option<usize> {
+        self.max_data_len
+    }
+
+    /// The maximum length of strings including the expanded HRP, if any
+    pub fn max_code_len(&self) -> Option<usize> {
+        self.max_code_len
+    }
 
-        let modulus = match u5String::from_str(modulus_str) {
-            Ok(s) => s,
-            Err(e) => panic!("Modulus string \"{modulus_str}\" was not a u5 string: {e}"),
+    /// Whether strings must, may or must not have an HRP
+    pub fn hrp_policy(&self) -> HrpPolicy {
+        self.hrp_policy
+    }
+
+    /// Check that a string (with HRP) is allowed by the checksum's HRP policy
+    /// and length limits, without checking the checksum itself
+    pub fn check_format(&self, s: &str) -> Result<(), String> {
+        self.check_format_with_extra(s, 0)
+    }
+
+    /// Check that a string (with HRP) would be allowed by the checksum's HRP
+    /// policy and length limits after `extra` more data characters were added
+    fn check_format_with_extra(&self, s: &str, extra: usize) -> Result<(), String> {
+        match (s.rsplit_once('1'), self.hrp_policy) {
+            (Some(_), HrpPolicy::Forbidden) => {
+                return Err("string has an HRP, which is not allowed".into());
+            }
+            (Some(("", _)), HrpPolicy::Required) => return Err("string has an empty HRP".into()),
+            (None, HrpPolicy::Required) => return Err("string has no HRP".into()),
+            _ => {}
+        }
+        let data_len = data_part_len(s) + extra;
+        if let Some(max) = self.max_data_len.filter(|&max| data_len > max) {
+            return Err(format!(
+                "data part has {data_len} characters, more than the maximum {max}"
+            ));
+        }
+        let code_len = expanded_len(s) + extra;
+        if let Some(max) = self.max_code_len.filter(|&max| code_len > max) {
+            return Err(format!(
+                "string has {code_len} characters including the expanded HRP, \
+                 more than the code length {max}"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parse a string (with HRP) into u5s, mapping it into our field
+    /// representation, or return an error if it has invalid characters
+    fn try_parse_input(&self, s: &str) -> Result<u5String, String> {
+        u5String::from_hrpstring(s).map(|input| self.representation.string_to_u5(&input))
+    }
+
+    /// Convert a position in a parsed string, counting from 0 at the end, to the
+    /// index of the corresponding character of the original string
+    ///
+    /// Returns `None` if the position falls within the expanded HRP, where a
+    /// single character of the original string corresponds to two positions.
+    fn char_index(s: &str, position: usize) -> Option<usize> {
+        let data_len = match s.rsplit_once('1') {
+            Some((_, data)) => data.chars().count(),
+            None => s.chars().count(),
         };
-        let residue = match u5String::from_str(residue_str) {
-            Ok(s) => s,
-            Err(e) => panic!("Residue string \"{residue_str}\" was not a u5 string: {e}"),
+        if position < data_len {
+            Some(s.chars().count() - 1 - position)
+        } else {
+            None
+        }
+    }
+
+    /// Replace every erasure marker (see `ERASURE_CHARS`) in a string by the
+    /// zero character, returning the new string along with the indices of the
+    /// erased characters, which also include `extra` indices, in order
+    fn mark_erasures(s: &str, extra: &[usize]) -> Result<(String, Vec<usize>), String> {
+        let data_start = match s.rsplit_once('1') {
+            Some((hrp, _)) => hrp.chars().count() + 1,
+            None => 0,
         };
-        Checksum { modulus, residue }
+        let mut chars: Vec<char> = s.chars().collect();
+        let mut erasures = extra.to_vec();
+        for (idx, ch) in chars.iter_mut().enumerate() {
+            if ERASURE_CHARS.contains(ch) {
+                erasures.push(idx);
+                *ch = 'q';
+            }
+        }
+        erasures.sort_unstable();
+        erasures.dedup();
+        for &idx in &erasures {
+            if idx < data_start {
+                return Err(format!("erasure at index {idx} is within the HRP"));
+            } else if idx >= chars.len() {
+                return Err(format!("erasure at index {idx} is beyond the string"));
+            }
+        }
+        Ok((chars.into_iter().collect(), erasures))
+    }
+
+    /// Convert a field element to a character of the string, in the given case
+    fn to_char(&self, value: u5, uppercase: bool) -> char {
+        let ch = self
+            .representation
+            .from_u5(value)
+            .to_string()
+            .chars()
+            .next()
+            .unwrap();
+        if uppercase {
+            ch.to_ascii_uppercase()
+        } else {
+            ch
+        }
+    }
+
+    /// Add error values to the characters at the given positions (counting
+    /// from 0 at the end) of a string, returning the corrected string
+    ///
+    /// Corrections at any of the character indices in `erasures` are reported
+    /// as filled erasures; erasure markers are treated as zero characters.
+    pub(crate) fn apply_corrections(
+        &self,
+        s: &str,
+        errors: &[(usize, u5)],
+        erasures: &[usize],
+    ) -> Result<Correction, String> {
+        let uppercase = !s.chars().any(|c| c.is_lowercase());
+        let mut chars: Vec<char> = s.chars().collect();
+        let mut filled = Vec::with_capacity(erasures.len());
+        let mut corrections = Vec::with_capacity(errors.len());
+        for &(position, error) in errors {
+            let idx = match Checksum::char_index(s, position) {
+                Some(idx) => idx,
+                None => return Err(format!("error at position {position} is within the HRP")),
+            };
+            let old = chars[idx];
+            let old_value = if ERASURE_CHARS.contains(&old) {
+                u5::ZERO
+            } else {
+                self.representation.to_u5(u5::from_char(old)?)
+            };
+            let new = self.to_char(old_value + error, uppercase);
+            chars[idx] = new;
+            if erasures.contains(&idx) {
+                filled.push((idx, old, new));
+            } else {
+                corrections.push((idx, old, new));
+            }
+        }
+        filled.sort_by_key(|&(idx, _, _)| idx);
+        corrections.sort_by_key(|&(idx, _, _)| idx);
+        Ok(Correction {
+            corrected: chars.into_iter().collect(),
+            filled,
+            corrections,
+        })
+    }
+
+    /// The generator of the checksum, as a polynomial
+    pub fn generator(&self) -> Poly<u5> {
+        Poly::from_le_u5string(&self.modulus)
+    }
+
+    /// The target residue, which the residue of a valid string must equal
+    pub fn target_residue(&self) -> &u5String {
+        &self.residue
+    }
+
+    /// The length of the checksum, in characters
+    pub fn checksum_len(&self) -> usize {
+        self.residue.len()
+    }
+
+    /// The map from the field representation the checksum is defined over to
+    /// our `u5` representation
+    pub fn representation(&self) -> Isomorphism {
+        self.representation
+    }
+
+    /// Determine the BCH structure of the checksum's generator
+    pub fn bch_structure(&self) -> Result<&BchStructure, String> {
+        self.bch
+            .get_or_init(|| BchStructure::analyze(&self.generator()))
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    /// Compute the residue of a string (with HRP), plus the target residue
+    ///
+    /// This is zero if and only if the string is valid. Otherwise it is the
+    /// remainder, mod the generator, of the error pattern that was added to
+    /// some valid string. Returns an error if the string has an invalid
+    /// character.
+    pub fn residue(&self, s: &str) -> Result<u5String, String> {
+        Ok(self.polymod(&self.try_parse_input(s)?))
+    }
+
+    /// Evaluate the residue of a string (with HRP) at the consecutive roots
+    /// r, rβ, ..., rβ^{δ-2} of the generator (see `BchStructure`)
+    ///
+    /// These are the syndromes used for BCH decoding. If errors e_j were
+    /// added at positions j, counting from 0 at the end of the string, then
+    /// the ith syndrome is the sum of e_j (rβ^i)^j.
+    pub fn syndromes(&self, s: &str) -> Result<Vec<ExtElem>, String> {
+        let bch = self.bch_structure()?;
+        let residue = Poly::from_be_u5string(&self.residue(s)?);
+        Ok(bch
+            .consecutive_roots()
+            .into_iter()
+            .map(|root| bch.field.coerce(residue.evaluate_in(root)))
+            .collect())
+    }
+
+    /// Fill in the erased characters of a string (with HRP)
+    ///
+    /// Erasures are marked with any of the characters in `ERASURE_CHARS`, and
+    /// may appear anywhere after the HRP, including in the checksum itself.
+    /// Since the checksum is linear, filling them amounts to solving a linear
+    /// system with one equation per checksum character; so up to
+    /// `checksum_len()` erasures can be filled, provided that the system has a
+    /// unique solution. (For up to `BchStructure::detectable_errors` erasures
+    /// it is guaranteed to have one, as long as the string is not longer than
+    /// the code length.)
+    ///
+    /// Returns an error if the string is not allowed by the checksum's HRP
+    /// policy or length limit, if any other characters are invalid, or if the
+    /// erasures cannot be uniquely filled.
+    pub fn fill_erasures(&self, s: &str) -> Result<Correction, String> {
+        self.check_format(s)?;
+        let (zeroed, erasures) = Checksum::mark_erasures(s, &[])?;
+        let n_chars = s.chars().count();
+        let residue = self.polymod(&self.try_parse_input(&zeroed)?);
+        if erasures.is_empty() {
+            if residue.is_all_zero() {
+                return Ok(Correction {
+                    corrected: s.into(),
+                    filled: vec![],
+                    corrections: vec![],
+                });
+            } else {
+                return Err("string has no erasures, and is invalid".into());
+            }
+        }
+        if erasures.len() > self.checksum_len() {
+            return Err(format!(
+                "{} erasures is more than the checksum length {}",
+                erasures.len(),
+                self.checksum_len()
+            ));
+        }
+
+        // Putting the value v at position j (counting from the end) adds
+        // v * (x^j mod g) to the residue, which we need to cancel out.
+        let generator = self.generator();
+        let columns: Vec<Vec<u5>> = erasures
+            .iter()
+            .map(|&idx| {
+                let monomial = Poly::monomial(u5::ONE, n_chars - 1 - idx);
+                let reduced = monomial.div_rem(&generator).1;
+                reduced.to_be_u5string(self.checksum_len())[..].to_vec()
+            })
+            .collect();
+        let matrix = Matrix::from_columns(self.checksum_len(), &columns);
+        let values = matrix
+            .solve(&residue[..])
+            .map_err(|e| format!("erasures cannot be uniquely filled: {e}"))?;
+
+        let errors: Vec<(usize, u5)> = erasures
+            .iter()
+            .zip(values)
+            .map(|(&idx, value)| (n_chars - 1 - idx, value))
+            .collect();
+        let correction = self.apply_corrections(s, &errors, &erasures)?;
+        if !self.validate_checksum(&correction.corrected) {
+            return Err(format!("filled string {} is invalid", correction.corrected));
+        }
+        Ok(correction)
+    }
+
+    /// Attempt to correct substitution errors in a string (with HRP)
+    ///
+    /// Up to `BchStructure::correctable_errors` errors can be corrected, as
+    /// long as the string (including its expanded HRP) is no longer than the
+    /// length of the code. Errors in the HRP itself cannot be corrected. If the
+    /// string is already valid it is returned unchanged, with no corrections.
+    ///
+    /// Any characters marked as erased (see `ERASURE_CHARS`) are handled as in
+    /// `correct_with_erasures`.
+    ///
+    /// Note that if there are more errors than can be corrected, this function
+    /// may either return an error or "correct" the string to a different valid
+    /// string.
+    pub fn correct(&self, s: &str) -> Result<Correction, String> {
+        self.correct_with_erasures(s, &[])
+    }
+
+    /// Attempt to fill erasures and correct substitution errors in a string
+    /// (with HRP) simultaneously
+    ///
+    /// The erased characters are those marked with one of `ERASURE_CHARS`,
+    /// along with those at the character indices `erasures`, whose contents are
+    /// ignored. With ρ erasures, up to ν further errors can be corrected as
+    /// long as 2ν + ρ is less than `BchStructure::designed_distance`. The
+    /// returned `Correction` lists every erasure as filled (even if its
+    /// character was correct), separately from the corrected errors.
+    ///
+    /// As with `correct`, exceeding these limits may result in "correcting"
+    /// the string to a different valid string.
+    pub fn correct_with_erasures(&self, s: &str, erasures: &[usize]) -> Result<Correction, String> {
+        self.check_format(s)?;
+        let (zeroed, erasures) = Checksum::mark_erasures(s, erasures)?;
+        let input = self.try_parse_input(&zeroed)?;
+        let residue = Poly::from_be_u5string(&self.polymod(&input));
+        if residue.is_zero() && erasures.is_empty() {
+            return Ok(Correction {
+                corrected: s.into(),
+                filled: vec![],
+                corrections: vec![],
+            });
+        }
+        let bch = self.bch_structure()?;
+        let syndromes: Vec<ExtElem> = bch
+            .consecutive_roots()
+            .into_iter()
+            .map(|root| residue.evaluate_in(root))
+            .collect();
+        let n_chars = s.chars().count();
+        let positions: Vec<usize> = erasures.iter().map(|&idx| n_chars - 1 - idx).collect();
+        let errors = decode::decode_with_erasures(bch, &syndromes, input.len(), &positions)
+            .map_err(|e| format!("uncorrectable: {e}"))?;
+
+        let correction = self
+            .apply_corrections(s, &errors, &erasures)
+            .map_err(|e| format!("uncorrectable: {e}"))?;
+        // If the generator has roots outside its run of consecutive roots,
+        // the decoder only cancels the syndromes, so the result may be invalid
+        if !self.validate_checksum(&correction.corrected) {
+            return Err(format!(
+                "uncorrectable: corrected string {} is invalid",
+                correction.corrected
+            ));
+        }
+        Ok(correction)
+    }
+
+    /// Locate the likely errors in a string (with HRP), without correcting them
+    ///
+    /// Returns the character indices, in order, of any characters outside the
+    /// HRP which are not in the alphabet, or if there are none, of the errors
+    /// that `correct` would fix. Failing that, it looks one error further for
+    /// a single nearest valid string (see `listdecode`).
+    ///
+    /// No corrected string is returned, since for strings like addresses it is
+    /// safer to have the user re-check the flagged characters than to trust a
+    /// correction, which may be wrong if there are too many errors.
+    ///
+    /// Returns an empty list for a valid string, and an error if the string is
+    /// not allowed by the checksum's HRP policy or length limit, or if the
+    /// errors cannot be located.
+    pub fn locate_errors(&self, s: &str) -> Result<Vec<usize>, String> {
+        self.check_format(s)?;
+        let data_start = match s.rsplit_once('1') {
+            Some((hrp, _)) => hrp.chars().count() + 1,
+            None => 0,
+        };
+        let invalid: Vec<usize> = s
+            .chars()
+            .enumerate()
+            .skip(data_start)
+            .filter(|&(_, ch)| u5::from_char(ch).is_err())
+            .map(|(idx, _)| idx)
+            .collect();
+        if !invalid.is_empty() {
+            return Ok(invalid);
+        }
+        if let Ok(correction) = self.correct(s) {
+            return Ok(correction
+                .corrections
+                .into_iter()
+                .map(|(idx, _, _)| idx)
+                .collect());
+        }
+
+        // Since we need not commit to a correction, we can also look one error
+        // beyond the unique decoding radius, and report the nearest valid
+        // string if it is the only one there.
+        let bch = self.bch_structure()?;
+        let max_distance = (bch.correctable_errors() + 1).min(bch.detectable_errors());
+        let list = listdecode::list_decode(self, s, max_distance, LOCATE_WORK_LIMIT, None)?;
+        match &list.candidates[..] {
+            [] => Err(format!("no valid string within distance {max_distance}")),
+            [candidate] if list.complete => {
+                Ok(candidate.changes.iter().map(|&(idx, _, _)| idx).collect())
+            }
+            [first, second, ..] if first.distance() < second.distance() && list.complete => {
+                Ok(first.changes.iter().map(|&(idx, _, _)| idx).collect())
+            }
+            _ => Err(format!(
+                "{} valid strings within distance {max_distance}",
+                list.candidates.len()
+            )),
+        }
+    }
+
+    /// Evaluate the residue of a string (with HRP) at every root of the
+    /// generator, returning (root, evaluation) pairs
+    pub fn root_evaluations(&self, s: &str) -> Result<Vec<(ExtElem, ExtElem)>, String> {
+        let bch = self.bch_structure()?;
+        let residue = Poly::from_be_u5string(&self.residue(s)?);
+        Ok(bch
+            .roots
+            .iter()
+            .map(|&root| (root, bch.field.coerce(residue.evaluate_in(root))))
+            .collect())
     }
 
     /// Compute the residue of a string, plus the target residue
     fn polymod(&self, input: &u5String) -> u5String {
-        /// Helper function to multiply the current remainder by x
-        fn shift(checksum: &Checksum, result: &mut [u5]) {
-            // Store current coefficient of x^{n-1}, which will become
-            // x^n (and get reduced)
-            let xn = result[0];
-            // Simply shift x^0 through x^{n-1} up one, and set x^0 to 0
-            for i in 1..result.len() {
-                result[i - 1] = result[i];
-            }
-            result[result.len() - 1] = u5::from(0);
-            // Then reduce x^n mod the generator. We need to read the generator
-            // backward for endianness reasons (well, because the generator is
-            // a polynomial stored with the ith coefficient in position i, while
-            // our target string sa the ith coefficient in position (n-i). Also
-            // we need to skip the final 1 coefficient, which is implicit in
-            // our algorithm.
-            let mod_iter = checksum.modulus[..checksum.modulus.len() - 1].iter().rev();
-            for (i, ch) in mod_iter.enumerate() {
-                result[i] += *ch * xn;
-            }
+        self.polymod_with(input, u5::mul_table)
+    }
+
+    /// Helper function to multiply the current remainder by x
+    fn shift<M: Fn(u5, u5) -> u5>(&self, result: &mut [u5], mul: &M) {
+        // Store current coefficient of x^{n-1}, which will become
+        // x^n (and get reduced)
+        let xn = result[0];
+        // Simply shift x^0 through x^{n-1} up one, and set x^0 to 0
+        for i in 1..result.len() {
+            result[i - 1] = result[i];
         }
+        result[result.len() - 1] = u5::from(0);
+        // Then reduce x^n mod the generator. We need to read the generator
+        // backward for endianness reasons (well, because the generator is
+        // a polynomial stored with the ith coefficient in position i, while
+        // our target string sa the ith coefficient in position (n-i). Also
+        // we need to skip the final 1 coefficient, which is implicit in
+        // our algorithm.
+        let mod_iter = self.modulus[..self.modulus.len() - 1].iter().rev();
+        for (i, ch) in mod_iter.enumerate() {
+            result[i] += mul(*ch, xn);
+        }
+    }
 
+    /// Compute the residue of a string, plus the target residue, using the
+    /// given GF(32) multiplication algorithm
+    pub(crate) fn polymod_with<M: Fn(u5, u5) -> u5>(&self, input: &u5String, mul: M) -> u5String {
         // 3. Loop through the string, interpreting it as a polynomial in
         // GF(32). Continually mod it out by the checksum generator
         // Here {n} represents the GF(32) element whose binary encoding
@@ -121,7 +676,7 @@ impl Checksum {
         let residue_len = ret.len();
         ret[residue_len - 1] = u5::from(1); // start with the polynomial 1
         for ch in &input[..] {
-            shift(self, &mut ret[..]);
+            self.shift(&mut ret[..], &mul);
             ret[residue_len - 1] += *ch;
         }
         // 4. Add the residue to it
@@ -132,29 +687,42 @@ impl Checksum {
         u5String::from(ret)
     }
 
+    /// Create an engine which computes the checksum of a string incrementally
+    pub fn engine(&self) -> ChecksumEngine<'_> {
+        let mut state = vec![u5::ZERO; self.residue.len()];
+        state[self.residue.len() - 1] = u5::ONE; // start with the polynomial 1
+        ChecksumEngine {
+            checksum: self,
+            state,
+        }
+    }
+
     /// Compute the checksum of a string (with HRP) and tack it onto the end
+    ///
+    /// Panics if the string has invalid characters, or is not allowed by the
+    /// checksum's HRP policy or length limit once the checksum is added.
     pub fn checksum(&self, s: &str) -> String {
+        match self.try_checksum(s) {
+            Ok(summed) => summed,
+            Err(e) => panic!("Could not checksum \"{s}\": {e}"),
+        }
+    }
+
+    /// Compute the checksum of a string (with HRP) and tack it onto the end,
+    /// returning an error rather than panicking if it cannot be checksummed
+    pub fn try_checksum(&self, s: &str) -> Result<String, String> {
+        // 0. Check that the string will have an acceptable HRP and length
+        self.check_format_with_extra(s, self.residue.len())?;
         // 1. Parse the string from ASCII into u5
-        let mut input = match u5String::from_hrpstring(s) {
-            Ok(s) => s,
-            Err(e) => panic!("String to checksum \"{s}\" was not a u5 string: {e}"),
-        };
+        let mut input = self.try_parse_input(s)?;
         // 2. Suffix some 0s onto the end, which we will replace by the checksum
-        let pre_checksum_len = input.len();
-        println!(
-            "len {pre_checksum_len}   residue len {}",
-            self.residue.len()
-        );
         for _ in 0..self.residue.len() {
             input.push(u5::from(0));
         }
-        // 3. Compute its checksum
+        // 3. Compute its checksum, and map it back to the original representation
         let checksum = self.polymod(&input);
-        // 4. Tack it onto the end
-        for i in 0..checksum.len() {
-            input[pre_checksum_len + i] = checksum[i];
-        }
-        // 5. Tack it onto the original string and return
+        let checksum = self.representation.string_from_u5(&checksum);
+        // 4. Tack it onto the original string and return
         let mut ret = String::with_capacity(s.len() + checksum.len());
         ret.push_str(s);
         ret.push_str(&if s.chars().any(|c| c.is_lowercase()) {
@@ -162,40 +730,1114 @@ impl Checksum {
         } else {
             checksum.to_string().to_uppercase()
         });
-        ret
+        Ok(ret)
     }
 
     /// Check whether an already-checksummed string is valid
     pub fn validate_checksum(&self, s: &str) -> bool {
+        self.validate(s).is_ok()
+    }
+
+    /// Check whether an already-checksummed string is valid, returning an
+    /// error which says why not if it is invalid
+    pub fn validate(&self, s: &str) -> Result<(), String> {
+        // 0. Check that the string has an acceptable HRP and length
+        self.check_format(s)?;
         // 1. Parse the string from ASCII into u5
-        let input = match u5String::from_hrpstring(s) {
-            Ok(s) => s,
-            Err(e) => panic!("String to checksum \"{s}\" was not a u5 string: {e}"),
-        };
+        let input = self.try_parse_input(s)?;
         // 2. Compute its checksum and confirm the residue is 0
-        self.polymod(&input).is_all_zero()
+        if self.polymod(&input).is_all_zero() {
+            Ok(())
+        } else {
+            Err("checksum does not match".into())
+        }
+    }
+
+    /// Check whether an already-checksummed string is valid, in constant time
+    ///
+    /// The HRP and the length of the string are treated as public, but the
+    /// data characters are not: they are parsed, mapped into our field
+    /// representation and reduced by the generator without any branches or
+    /// table lookups which depend on them. Use this rather than
+    /// `validate_checksum` for strings carrying secret data, such as codex32
+    /// shares.
+    pub fn ct_validate_checksum(&self, s: &str) -> bool {
+        if self.check_format(s).is_err() {
+            return false;
+        }
+        let (hrp, data) = s.rsplit_once('1').unwrap_or(("", s));
+        let data: Vec<u32> = data.chars().map(u32::from).collect();
+        self.ct_validate_words(hrp, &data) != 0
+    }
+
+    /// Constant-time validation of a string whose data characters are given as
+    /// words holding their Unicode scalar values
+    ///
+    /// Returns an all-1s mask if the string is valid, and an all-0s mask if it
+    /// has an invalid character or its checksum does not match.
+    pub(crate) fn ct_validate_words<W: ct::Word>(&self, hrp: &str, data: &[W]) -> W {
+        let expanded = u5String::from_hrpstring(&format!("{hrp}1"))
+            .expect("an empty data part has no invalid characters");
+        let mut input: Vec<W> = self.representation.string_to_u5(&expanded)[..]
+            .iter()
+            .map(|&x| W::constant(u8::from(x).into()))
+            .collect();
+        let basis_images = [1, 2, 4, 8, 16].map(|x| self.representation.to_u5(u5::from(x)));
+        let mut valid = W::constant(u32::MAX);
+        for &c in data {
+            let (value, found) = ct::from_char(c);
+            valid = valid & found;
+            input.push(ct::linear_map(&basis_images, value));
+        }
+        let generator = &self.modulus[..self.modulus.len() - 1];
+        let residue = ct::polymod(generator, &self.residue[..], &input);
+        valid & ct::all_zero(&residue)
     }
 }
 
-/*
-/// The codex32 checksum
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
-pub struct Codex32;
-impl Checksum for Codex32 {
+/// The length of the data part of a string, i.e. everything after the last
+/// '1', or the whole string if there is no '1'
+fn data_part_len(s: &str) -> usize {
+    match s.rsplit_once('1') {
+        Some((_, data)) => data.chars().count(),
+        None => s.chars().count(),
+    }
 }
 
-/// The bech32 checksum
+/// Which of the bech32 and bech32m checksums a string is valid under
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Bech32Variant {
+    /// Valid under neither checksum, or not a well-formed bech32 string
+    Neither,
+    /// Valid under the bech32 checksum (BIP-173) only
+    Bech32,
+    /// Valid under the bech32m checksum (BIP-350) only
+    Bech32m,
+    /// Valid under both checksums (impossible for a single string, since the
+    /// two differ only in their target residues)
+    Both,
+}
+
+impl fmt::Display for Bech32Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Bech32Variant::Neither => "neither",
+            Bech32Variant::Bech32 => "bech32",
+            Bech32Variant::Bech32m => "bech32m",
+            Bech32Variant::Both => "both",
+        })
+    }
+}
+
+/// Check the rules which BIP-173 imposes on the format of bech32 and bech32m
+/// strings, beyond their checksums
+fn check_bech32_format(s: &str) -> Result<(), String> {
+    if s.len() > 90 {
+        return Err(format!("string has {} characters, more than 90", s.len()));
+    }
+    if s.chars().any(|c| c.is_lowercase()) && s.chars().any(|c| c.is_uppercase()) {
+        return Err("string has mixed case".into());
+    }
+    let Some((hrp, data)) = s.rsplit_once('1') else {
+        return Err("string has no separator".into());
+    };
+    if hrp.is_empty() || hrp.len() > 83 {
+        return Err(format!("HRP has {} characters, not 1 to 83", hrp.len()));
+    }
+    if let Some(c) = hrp.chars().find(|c| !(33..=126).contains(&u32::from(*c))) {
+        return Err(format!("HRP has invalid character {c:?}"));
+    }
+    if data.len() < 6 {
+        return Err("data part is too short to hold a checksum".into());
+    }
+    Ok(())
+}
+
+/// Determine whether a string is valid as bech32 (BIP-173), as bech32m
+/// (BIP-350), as both or as neither
+///
+/// As well as the checksums, this checks the rules which BIP-173 imposes on
+/// the format of strings, such as their 90-character limit. A string which is
+/// well-formed but valid under the wrong checksum is a sign that it was
+/// encoded with the wrong variant, e.g. a taproot address encoded with bech32.
+pub fn bech32_variant(s: &str) -> Bech32Variant {
+    if check_bech32_format(s).is_err() {
+        return Bech32Variant::Neither;
+    }
+    match (Bech32::validate_checksum(s), Bech32m::validate_checksum(s)) {
+        (false, false) => Bech32Variant::Neither,
+        (true, false) => Bech32Variant::Bech32,
+        (false, true) => Bech32Variant::Bech32m,
+        (true, true) => Bech32Variant::Both,
+    }
+}
+
+/// The length of a string including its expanded HRP, i.e. the number of
+/// characters covered by the checksum
+fn expanded_len(s: &str) -> usize {
+    match s.rsplit_once('1') {
+        Some((hrp, data)) => 2 * hrp.len() + 1 + data.chars().count(),
+        None => 1 + s.chars().count(),
+    }
+}
+
+/// Choose between codex32 and long codex32 for a string, as BIP-93 does,
+/// returning the name of the chosen checksum
+///
+/// If `checksummed` is false, the string is yet to be checksummed: codex32
+/// is used for data parts of up to 80 characters, so that the result has at
+/// most 93, and long codex32 otherwise. If it is true, the string already has
+/// a checksum: codex32 is used for data parts of up to 93 characters and long
+/// codex32 for those of at least 96, and data parts of 94 or 95 characters
+/// are invalid. Like BIP-93, these count only the data part, not the HRP.
+pub fn select_codex32(s: &str, checksummed: bool) -> Result<&'static str, String> {
+    let data_len = data_part_len(s);
+    let max_short = Codex32::MAX_DATA_LEN.unwrap();
+    let max_short_unsummed = max_short - Codex32::CHECKSUM_LEN;
+    if !checksummed {
+        if data_len <= max_short_unsummed {
+            Ok(Codex32::NAME)
+        } else {
+            Ok(LongCodex32::NAME)
+        }
+    } else if data_len <= max_short {
+        Ok(Codex32::NAME)
+    } else if data_len > max_short_unsummed + LongCodex32::CHECKSUM_LEN {
+        Ok(LongCodex32::NAME)
+    } else {
+        Err(format!(
+            "data part has {data_len} characters, which is invalid for codex32 strings"
+        ))
+    }
+}
+
+/// An engine which computes a checksum incrementally, one character at a time
+///
+/// Feeding the engine an HRP with `input_hrp`, followed by the characters of
+/// the data part, gives the same results as passing the whole string to the
+/// methods of `Checksum`, without needing the whole string up front.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ChecksumEngine<'a> {
+    checksum: &'a Checksum,
+    /// The residue of the input so far, without the target residue
+    state: Vec<u5>,
+}
+
+impl<'a> ChecksumEngine<'a> {
+    /// Input the HRP of the string, which must be done before any other input
+    ///
+    /// Like `u5String::from_hrpstring`, this inputs the high bits of each
+    /// character, then a zero, then the low bits of each character. So for
+    /// strings with no HRP (or no '1' at all), `input_hrp("")` must still be
+    /// called, to input the zero.
+    pub fn input_hrp(&mut self, hrp: &str) {
+        for ch in hrp.bytes() {
+            self.input_u5(u5::from(ch.to_ascii_lowercase() >> 5));
+        }
+        self.input_u5(u5::ZERO);
+        for ch in hrp.bytes() {
+            self.input_u5(u5::from(ch & 0x1f));
+        }
+    }
+
+    /// Input a single character of the data part of the string
+    pub fn input_char(&mut self, ch: char) -> Result<(), String> {
+        self.input_u5(u5::from_char(ch)?);
+        Ok(())
+    }
+
+    /// Input a single value, as would be obtained by parsing a character with
+    /// `u5::from_char`
+    pub fn input_u5(&mut self, value: u5) {
+        let value = self.checksum.representation.to_u5(value);
+        self.checksum.shift(&mut self.state, &u5::mul_table);
+        let len = self.state.len();
+        self.state[len - 1] += value;
+    }
+
+    /// The residue of the input so far, plus the target residue, as would be
+    /// returned by `Checksum::residue`; this is zero if the input is valid
+    pub fn residue(&self) -> u5String {
+        let ret: Vec<u5> = self
+            .state
+            .iter()
+            .zip(&self.checksum.residue[..])
+            .map(|(&x, &y)| x + y)
+            .collect();
+        u5String::from(ret)
+    }
+
+    /// Compute the checksum of the input, which should be appended to it to
+    /// make it valid
+    pub fn finalize(mut self) -> u5String {
+        for _ in 0..self.state.len() {
+            self.checksum.shift(&mut self.state, &u5::mul_table);
+        }
+        self.checksum.representation.string_from_u5(&self.residue())
+    }
+}
+
+/// A checksum whose parameters are fixed at compile time
+///
+/// `N` is the length of the checksum in characters. The parameters are given
+/// as strings, as described in the module documentation, and are parsed and
+/// checked at compile time; using a type whose strings are malformed, or whose
+/// other constants are inconsistent, is a compile error. The `Checksum` type
+/// is the runtime equivalent, which can be obtained with `to_checksum`.
+pub trait StaticChecksum<const N: usize> {
+    /// The name of the checksum, as used by `get_checksums`
+    const NAME: &'static str;
+    /// The modulus, in little-endian order, including the final 'p'
+    const MODULUS_STRING: &'static str;
+    /// The target residue, which the residue of a valid string must equal
+    const RESIDUE_STRING: &'static str;
+    /// The maximum length of the code, in characters including the expanded
+    /// HRP, for which the BCH error detection guarantees hold
+    const MAX_CODE_LENGTH: usize;
+    /// The maximum length of the data part, in characters including the
+    /// checksum, for checksums whose standard limits the data part rather
+    /// than the whole code, as BIP-93 does for codex32
+    ///
+    /// If set, this replaces the limit on the code length.
+    const MAX_DATA_LEN: Option<usize> = None;
+
+    /// The length of the checksum, in characters
+    const CHECKSUM_LEN: usize = N;
+    /// The coefficients of the generator, in little-endian order, without the
+    /// implicit leading 1
+    const GENERATOR: [u5; N] = generator_coefficients(Self::MODULUS_STRING);
+    /// The target residue, in big-endian order like the residue itself
+    const TARGET_RESIDUE: [u5; N] = base32::u5_array(Self::RESIDUE_STRING);
+
+    /// Compile-time check that the constants are consistent
+    const VALID: () = {
+        assert!(N > 0, "checksum must be nonempty");
+        assert!(
+            Self::GENERATOR[0].to_u8() != 0,
+            "generator must not be divisible by x",
+        );
+        assert!(
+            Self::MAX_CODE_LENGTH > N,
+            "code must be longer than its checksum",
+        );
+        if let Some(max_data_len) = Self::MAX_DATA_LEN {
+            assert!(max_data_len > N, "data part must fit the checksum");
+        }
+    };
+
+    /// Check that a string (with HRP) would be within the length limit after
+    /// `extra` more data characters were added
+    fn check_length(s: &str, extra: usize) -> Result<(), String> {
+        match Self::MAX_DATA_LEN {
+            Some(max) => {
+                let data_len = data_part_len(s) + extra;
+                if data_len > max {
+                    return Err(format!(
+                        "data part has {data_len} characters, more than the maximum {max}"
+                    ));
+                }
+            }
+            None => {
+                let code_len = expanded_len(s) + extra;
+                if code_len > Self::MAX_CODE_LENGTH {
+                    return Err(format!(
+                        "string has {code_len} characters including the expanded HRP, \
+                         more than the code length {}",
+                        Self::MAX_CODE_LENGTH
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compute the residue of a parsed string, plus the target residue
+    fn polymod(input: &[u5]) -> [u5; N] {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        // As in `Checksum::polymod_with`, start with the polynomial 1 and
+        // repeatedly multiply by x, reduce, and add the next character.
+        let mut ret = [u5::ZERO; N];
+        ret[N - 1] = u5::ONE;
+        for &ch in input {
+            let xn = ret[0];
+            ret.copy_within(1.., 0);
+            ret[N - 1] = ch;
+            for (i, coeff) in ret.iter_mut().enumerate() {
+                *coeff += Self::GENERATOR[N - 1 - i] * xn;
+            }
+        }
+        for (ch, target) in ret.iter_mut().zip(Self::TARGET_RESIDUE) {
+            *ch += target;
+        }
+        ret
+    }
+
+    /// Compute the residue of a string (with HRP), plus the target residue
+    fn residue(s: &str) -> Result<[u5; N], String> {
+        let input = u5String::from_hrpstring(s)?;
+        Ok(Self::polymod(&input[..]))
+    }
+
+    /// Compute the checksum of a string (with HRP) and tack it onto the end
+    fn checksum(s: &str) -> Result<String, String> {
+        Self::check_length(s, N)?;
+        let mut input = u5String::from_hrpstring(s)?;
+        for _ in 0..N {
+            input.push(u5::ZERO);
+        }
+        let checksum = u5String::from(Self::polymod(&input[..]).to_vec());
+        Ok(if s.chars().any(|c| c.is_lowercase()) {
+            format!("{s}{checksum}")
+        } else {
+            format!("{s}{}", checksum.to_string().to_uppercase())
+        })
+    }
+
+    /// Check whether an already-checksummed string is valid
+    fn validate_checksum(s: &str) -> bool {
+        if Self::check_length(s, 0).is_err() {
+            return false;
+        }
+        match Self::residue(s) {
+            Ok(residue) => residue == [u5::ZERO; N],
+            Err(_) => false,
+        }
+    }
+
+    /// Construct the equivalent runtime checksum
+    fn to_checksum() -> Checksum {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        let mut modulus = Self::GENERATOR.to_vec();
+        modulus.push(u5::ONE);
+        Checksum {
+            modulus: u5String::from(modulus),
+            residue: u5String::from(Self::TARGET_RESIDUE.to_vec()),
+            representation: Isomorphism::identity(),
+            max_data_len: Self::MAX_DATA_LEN,
+            max_code_len: Self::MAX_DATA_LEN
+                .is_none()
+                .then_some(Self::MAX_CODE_LENGTH),
+            hrp_policy: HrpPolicy::Optional,
+            bch: OnceLock::new(),
+        }
+    }
+}
+
+/// Parses a modulus string, which must have `N + 1` characters and end in
+/// 'p', into the coefficients of the generator other than the leading 1
+const fn generator_coefficients<const N: usize>(modulus: &str) -> [u5; N] {
+    let bytes = modulus.as_bytes();
+    assert!(bytes.len() == N + 1, "modulus has the wrong length");
+    assert!(
+        u5::from_char_const(bytes[N]).to_u8() == 1,
+        "modulus should end in 'p'",
+    );
+    let mut ret = [u5::ZERO; N];
+    let mut i = 0;
+    while i < N {
+        ret[i] = u5::from_char_const(bytes[i]);
+        i += 1;
+    }
+    ret
+}
+
+/// The bech32 checksum (BIP-173)
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 pub struct Bech32;
-impl Checksum for Bech32 {
+impl StaticChecksum<6> for Bech32 {
+    const NAME: &'static str = "bech32";
     const MODULUS_STRING: &'static str = "ja45kap";
     const RESIDUE_STRING: &'static str = "qqqqqp";
+    const MAX_CODE_LENGTH: usize = 1023;
+}
+
+/// The bech32m checksum (BIP-350), which replaces bech32 for segwit
+/// addresses of version 1 and above
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Bech32m;
+impl StaticChecksum<6> for Bech32m {
+    const NAME: &'static str = "bech32m";
+    const MODULUS_STRING: &'static str = "ja45kap";
+    const RESIDUE_STRING: &'static str = "4usv9r";
+    const MAX_CODE_LENGTH: usize = 1023;
+}
+
+/// The codex32 checksum (BIP-93)
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Codex32;
+impl StaticChecksum<13> for Codex32 {
+    const NAME: &'static str = "codex32";
+    const MODULUS_STRING: &'static str = "sscmleeeqg3mep";
+    const RESIDUE_STRING: &'static str = "secretshare32";
+    const MAX_CODE_LENGTH: usize = 93;
+    const MAX_DATA_LEN: Option<usize> = Some(93);
+}
+
+/// The long codex32 checksum (BIP-93), for strings too long for codex32
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct LongCodex32;
+impl StaticChecksum<15> for LongCodex32 {
+    const NAME: &'static str = "long-codex32";
+    const MODULUS_STRING: &'static str = "hyk9x4hx4ef6e20p";
+    const RESIDUE_STRING: &'static str = "secretshare32ex";
+    const MAX_CODE_LENGTH: usize = 1023;
 }
-*/
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use super::*;
     use crate::base32;
+    use crate::field::Field;
+    use crate::gf32::Gf32;
+    use crate::poly::Poly;
+
+    /// The parity checksum, for which a string is valid if its characters sum
+    /// to zero
+    pub(crate) fn parity() -> Checksum {
+        Checksum::new_in_representation("pp", "q", Isomorphism::identity())
+    }
+
+    #[test]
+    fn polymod_mul_strategies_agree() {
+        let input =
+            u5String::from_hrpstring("ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw").unwrap();
+        for checksum in get_checksums().values() {
+            assert_eq!(
+                checksum.polymod_with(&input, u5::mul_shift_add),
+                checksum.polymod_with(&input, u5::mul_table),
+            );
+        }
+    }
+
+    /// Computes `polymod` as (x^n + data) mod generator, plus target residue,
+    /// where `n` is the length of the data
+    fn poly_polymod(checksum: &Checksum, input: &u5String) -> u5String {
+        let modulus = Poly::from_le_u5string(&checksum.modulus);
+        let target = Poly::from_be_u5string(&checksum.residue);
+        let data = &Poly::from_be_u5string(input) + &Poly::monomial(u5::ONE, input.len());
+        let (_, rem) = data.div_rem(&modulus);
+        (&rem + &target).to_be_u5string(checksum.residue.len())
+    }
+
+    #[test]
+    fn polymod_matches_poly() {
+        for (name, checksum) in get_checksums() {
+            for s in [
+                "",
+                "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx",
+                "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw",
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                "ms10leetsllhdmn9m42vcsamx24zrxgs3qrl7ahwvhw4fnzrhve25gvezzyqqtum9pgv99ycma",
+            ] {
+                let input = u5String::from_hrpstring(s).unwrap();
+                assert_eq!(
+                    checksum.polymod(&input),
+                    poly_polymod(&checksum, &input),
+                    "checksum {name}, input {s}",
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn engine() {
+        let checksums = get_checksums();
+        for (name, valid) in [
+            ("bech32", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+            (
+                "codex32",
+                "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw",
+            ),
+            (
+                "codex32",
+                "MS12NAMEA320ZYXWVUTSRQPNMLKJHGFEDCAXRPP870HKKQRM",
+            ),
+        ] {
+            let checksum = &checksums[name];
+            let (hrp, data) = valid.rsplit_once('1').unwrap();
+            let data_len = data.len() - checksum.checksum_len();
+
+            // Residues agree with the whole-string ones at every point
+            let mut engine = checksum.engine();
+            engine.input_hrp(hrp);
+            for (i, ch) in data.chars().enumerate() {
+                engine.input_char(ch).unwrap();
+                let prefix = &valid[..hrp.len() + 2 + i];
+                assert_eq!(engine.residue(), checksum.residue(prefix).unwrap());
+            }
+            assert!(engine.residue().is_all_zero());
+
+            // Checksums agree with the whole-string ones
+            let mut engine = checksum.engine();
+            engine.input_hrp(hrp);
+            for ch in data[..data_len].chars() {
+                engine.input_u5(u5::from_char(ch).unwrap());
+            }
+            let expected = &data[data_len..].to_lowercase();
+            assert_eq!(engine.finalize().to_string(), *expected);
+        }
+
+        // Strings without an HRP, and in alternate representations
+        let alt = Checksum::new_in_representation(
+            "ja45kap",
+            "qqqqqq",
+            Isomorphism::from_reduction(0x25).unwrap(),
+        );
+        let summed = alt.checksum("1qpzry9x8");
+        let mut engine = alt.engine();
+        engine.input_hrp("");
+        for ch in "qpzry9x8".chars() {
+            engine.input_char(ch).unwrap();
+        }
+        assert_eq!(engine.finalize().to_string(), summed["1qpzry9x8".len()..]);
+
+        // Strings with no '1' at all are treated as having an empty HRP, and
+        // skipping `input_hrp` gives a different residue
+        let bech32 = &checksums["bech32"];
+        let summed = bech32.checksum("qpzry9x8");
+        let mut engine = bech32.engine();
+        engine.input_hrp("");
+        for ch in summed.chars() {
+            engine.input_char(ch).unwrap();
+        }
+        assert!(engine.residue().is_all_zero());
+        assert!(bech32.validate_checksum(&summed));
+        let mut engine = bech32.engine();
+        for ch in summed.chars() {
+            engine.input_char(ch).unwrap();
+        }
+        assert!(!engine.residue().is_all_zero());
+
+        let mut engine = checksums["bech32"].engine();
+        assert!(engine.input_char('b').is_err());
+    }
+
+    #[test]
+    fn static_checksums() {
+        fn check<C: StaticChecksum<N>, const N: usize>(valid: &str) {
+            let checksum = C::to_checksum();
+            let by_strings = Checksum::new_in_representation(
+                C::MODULUS_STRING,
+                C::RESIDUE_STRING,
+                Isomorphism::identity(),
+            );
+            let by_strings = match C::MAX_DATA_LEN {
+                Some(max_data_len) => by_strings.with_max_data_len(max_data_len),
+                None => by_strings.with_max_code_len(C::MAX_CODE_LENGTH),
+            };
+            assert_eq!(checksum, by_strings);
+            assert_eq!(checksum.checksum_len(), C::CHECKSUM_LEN);
+            let bch = checksum.bch_structure().unwrap();
+            assert_eq!(bch.length, C::MAX_CODE_LENGTH as u64);
+
+            assert!(C::validate_checksum(valid));
+            let unsummed = &valid[..valid.len() - N];
+            assert_eq!(C::checksum(unsummed).unwrap(), valid);
+            let upper = valid.to_uppercase();
+            assert_eq!(C::checksum(&upper[..upper.len() - N]).unwrap(), upper);
+            for s in [valid, unsummed] {
+                assert_eq!(C::residue(s).unwrap()[..], checksum.residue(s).unwrap()[..]);
+            }
+            assert!(!C::validate_checksum(&valid.replace('x', "q")));
+            assert!(!C::validate_checksum("b"));
+        }
+
+        check::<Bech32, 6>("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        check::<Codex32, 13>("ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw");
+        check::<LongCodex32, 15>(&LongCodex32::checksum("ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx").unwrap());
+        assert_eq!(Codex32::TARGET_RESIDUE, base32::u5_array("secretshare32"));
+        assert_eq!(Bech32::GENERATOR[0], u5::from_char('j').unwrap());
+    }
+
+    #[test]
+    fn bech32m_vectors() {
+        // Valid test vectors from BIP-350
+        let valid = [
+            "A1LQFN3A",
+            "a1lqfn3a",
+            "an83characterlonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11sg7hg6",
+            "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+            "11llllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllludsr8",
+            "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+            "?1v759aa",
+        ];
+        let bech32m = &get_checksums()["bech32m"];
+        for s in valid {
+            assert!(Bech32m::validate_checksum(s), "{s}");
+            assert!(bech32m.validate_checksum(s), "{s}");
+            assert_eq!(bech32_variant(s), Bech32Variant::Bech32m, "{s}");
+            let unsummed = &s[..s.len() - 6];
+            // (with no letters before the checksum, we output it in uppercase)
+            assert!(bech32m.checksum(unsummed).eq_ignore_ascii_case(s), "{s}");
+        }
+
+        // Invalid test vectors from BIP-350, with the reasons they are invalid
+        let invalid = [
+            "\u{20}1xj0phk",   // HRP character out of range
+            "\u{7f}1g6xzxy",   // HRP character out of range
+            "\u{80}1vctc34",   // HRP character out of range
+            "an84characterslonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11d6pts4", // overall max length exceeded
+            "qyrz8wqd2c9m",   // no separator character
+            "1qyrz8wqd2c9m",  // empty HRP
+            "y1b0jsk6g",      // invalid data character
+            "lt1igcx5c0",     // invalid data character
+            "in1muywd",       // too short checksum
+            "mm1crxm3i",      // invalid character in checksum
+            "au1s5cgom",      // invalid character in checksum
+            "M1VUXWEZ",       // checksum calculated with uppercase form of HRP
+            "16plkw9",        // empty HRP
+            "1p2gdwpf",       // empty HRP
+        ];
+        for s in invalid {
+            assert_eq!(bech32_variant(s), Bech32Variant::Neither, "{s}");
+        }
+        // The checksum alone rejects those which are invalid for reasons other
+        // than the format rules of BIP-173
+        for s in &invalid[6..12] {
+            assert!(!bech32m.validate_checksum(s), "{s}");
+        }
+    }
+
+    #[test]
+    fn bech32_variants() {
+        // Valid test vectors from BIP-173
+        for s in [
+            "A12UEL5L",
+            "a12uel5l",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+            "?1ezyfcl",
+        ] {
+            assert_eq!(bech32_variant(s), Bech32Variant::Bech32, "{s}");
+        }
+        assert_eq!(bech32_variant("A12UEL5l"), Bech32Variant::Neither);
+
+        // A taproot address, and the same address mistakenly encoded with bech32
+        let taproot = "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0";
+        assert_eq!(bech32_variant(taproot), Bech32Variant::Bech32m);
+        let wrong = Bech32::checksum(&taproot[..taproot.len() - 6]).unwrap();
+        assert_eq!(bech32_variant(&wrong), Bech32Variant::Bech32);
+
+        // Invalid segwit addresses from BIP-350 whose checksums are valid, but
+        // of the wrong variant for their witness versions
+        for s in [
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+            "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf",
+            "BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL",
+            // and a version 2 address from BIP-173, which became invalid
+            "bc1zw508d6qejxtdg4y5r3zarvaryvg6kdaj",
+        ] {
+            assert_eq!(bech32_variant(s), Bech32Variant::Bech32, "{s}");
+        }
+        // (BIP-350 re-encodes that last address with bech32m)
+        let v2 = "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs";
+        assert_eq!(bech32_variant(v2), Bech32Variant::Bech32m);
+        for s in [
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+            "tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47",
+        ] {
+            assert_eq!(bech32_variant(s), Bech32Variant::Bech32m, "{s}");
+        }
+        assert_eq!(Bech32Variant::Bech32.to_string(), "bech32");
+    }
+
+    #[test]
+    fn length_limits() {
+        let checksums = get_checksums();
+        let codex32 = &checksums["codex32"];
+        // 80 characters of data fit, with the checksum, in 93; like BIP-93,
+        // this does not count the HRP
+        let data = format!("ms10leet{}", "q".repeat(75));
+        let valid = codex32.try_checksum(&data).unwrap();
+        assert!(codex32.validate(&valid).is_ok());
+        assert!(Codex32::validate_checksum(&valid));
+        let data = format!("{data}q");
+        assert!(codex32.try_checksum(&data).is_err());
+        assert!(Codex32::checksum(&data).is_err());
+        // and an overlong string is rejected even if its residue is zero
+        let overlong = Checksum::new_in_representation(
+            Codex32::MODULUS_STRING,
+            Codex32::RESIDUE_STRING,
+            Isomorphism::identity(),
+        )
+        .checksum(&format!("ms10leet{}", "q".repeat(200)));
+        assert!(codex32
+            .validate(&overlong)
+            .unwrap_err()
+            .contains("more than the maximum 93"));
+        assert!(!Codex32::validate_checksum(&overlong));
+
+        assert_eq!(
+            codex32.validate("ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlx"),
+            Err("checksum does not match".into()),
+        );
+        assert!(codex32
+            .validate("ms10testsbxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw")
+            .is_err());
+
+        // Data parts of up to 93 characters validate, whatever the HRP
+        let data = format!("ms1{}", "q".repeat(80));
+        let valid = codex32.try_checksum(&data).unwrap();
+        assert_eq!(data_part_len(&valid), 93);
+        assert!(codex32.validate(&valid).is_ok());
+        assert!(Codex32::validate_checksum(&valid));
+        assert_eq!(Codex32::checksum(&data), Ok(valid));
+
+        // Other checksums count the expanded HRP against their code length
+        let bech32 = &checksums["bech32"];
+        let data = format!("bc1{}", "q".repeat(1023 - 5 - 6));
+        assert!(bech32.try_checksum(&data).is_ok());
+        assert!(Bech32::checksum(&data).is_ok());
+        let data = format!("{data}q");
+        assert!(bech32
+            .try_checksum(&data)
+            .unwrap_err()
+            .contains("more than the code length 1023"));
+        assert!(Bech32::checksum(&data).is_err());
+    }
+
+    #[test]
+    fn select_codex32_by_length() {
+        let long_vector = "MS100C8VSM32ZXFGUHPCHTLUPZRY9X8GF2TVDW0S3JN54KHCE6MUA7LQPZYGSFJD6AN074RXVCEMLH8WU3TK925ACDEFGHJKLMNPQRSTUVWXY06FHPV80UNDVARHRAK";
+        assert_eq!(select_codex32(long_vector, true), Ok("long-codex32"));
+        let short_vector = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+        assert_eq!(select_codex32(short_vector, true), Ok("codex32"));
+
+        for data_len in 0..200 {
+            let data = format!("ms1{}", "q".repeat(data_len));
+            let name = select_codex32(&data, false).unwrap();
+            assert_eq!(
+                name,
+                if data_len <= 80 {
+                    "codex32"
+                } else {
+                    "long-codex32"
+                }
+            );
+            // The checksummed string is always valid, and selects the same checksum
+            let summed = get_checksums()[name].checksum(&data);
+            assert_eq!(select_codex32(&summed, true), Ok(name));
+            // whereas the lengths in between codex32 and long codex32 are not
+            assert_eq!(
+                select_codex32(&data, true).is_err(),
+                data_len == 94 || data_len == 95,
+            );
+        }
+    }
+
+    #[test]
+    fn bch_structures() {
+        let checksums = get_checksums();
+        let distance = |name| {
+            let bch = checksums[name].bch_structure().unwrap();
+            (bch.designed_distance, bch.length)
+        };
+        assert_eq!(distance("bech32"), (4, 1023));
+        assert_eq!(distance("codex32"), (9, 93));
+        assert_eq!(distance("long-codex32"), (9, 1023));
+    }
+
+    #[test]
+    fn syndromes() {
+        let codex32 = &get_checksums()["codex32"];
+        let valid = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+        assert!(codex32.residue(valid).unwrap().is_all_zero());
+        let syndromes = codex32.syndromes(valid).unwrap();
+        assert_eq!(syndromes.len(), 8);
+        assert!(syndromes.iter().all(|&s| s == ExtElem::ZERO));
+        let evals = codex32.root_evaluations(valid).unwrap();
+        assert_eq!(evals.len(), 13);
+        assert!(evals.iter().all(|&(_, e)| e == ExtElem::ZERO));
+
+        // Replace the 6th-last character 'c' (24) with 'q' (0), i.e. add the
+        // error value 24 at position 5. Then the syndromes are 24 (rβ^i)^5.
+        let invalid = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9qmczlw";
+        assert!(!codex32.residue(invalid).unwrap().is_all_zero());
+        let bch = codex32.bch_structure().unwrap();
+        let error = ExtElem::from_u5(u5::from(24));
+        let syndromes = codex32.syndromes(invalid).unwrap();
+        for (root, syndrome) in bch.consecutive_roots().into_iter().zip(syndromes) {
+            assert_eq!(syndrome, error * root.pow(5));
+        }
+        for (root, eval) in codex32.root_evaluations(invalid).unwrap() {
+            assert_eq!(eval, error * root.pow(5));
+        }
+
+        // Invalid characters are reported, not panicked on
+        let typo = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9bmczlw";
+        assert!(codex32.residue(typo).is_err());
+        assert!(codex32.syndromes(typo).is_err());
+        assert!(codex32.root_evaluations(typo).is_err());
+    }
+
+    /// Substitutes characters of a string, where each substitution is given as
+    /// a character index and an amount to add to the character's value
+    fn add_errors(s: &str, errors: &[(usize, u8)]) -> String {
+        let mut chars: Vec<char> = s.chars().collect();
+        for &(idx, e) in errors {
+            let value = u5::from_char(chars[idx]).unwrap() + u5::from(e);
+            chars[idx] = value.to_string().chars().next().unwrap();
+            if s.chars().any(|c| c.is_uppercase()) {
+                chars[idx] = chars[idx].to_ascii_uppercase();
+            }
+        }
+        chars.into_iter().collect()
+    }
+
+    #[test]
+    fn correct() {
+        let checksums = get_checksums();
+        let cases = [
+            (
+                "bech32",
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                vec![(10, 3)],
+            ),
+            (
+                "bech32",
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                vec![(41, 31)],
+            ),
+            (
+                "codex32",
+                "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw",
+                vec![(4, 1), (20, 5), (33, 17), (47, 30)],
+            ),
+            (
+                "codex32",
+                "MS12NAMEA320ZYXWVUTSRQPNMLKJHGFEDCAXRPP870HKKQRM",
+                vec![(5, 8), (6, 8), (7, 8)],
+            ),
+            (
+                "codex32",
+                "ms10leetsllhdmn9m42vcsamx24zrxgs3qrl7ahwvhw4fnzrhve25gvezzyqqtum9pgv99ycma",
+                vec![(3, 2), (73, 2)],
+            ),
+        ];
+        for (name, valid, errors) in cases {
+            let checksum = &checksums[name];
+            let invalid = add_errors(valid, &errors);
+            assert!(!checksum.validate_checksum(&invalid));
+            let correction = checksum.correct(&invalid).unwrap();
+            assert_eq!(correction.corrected, valid);
+            let indices: Vec<usize> = correction.corrections.iter().map(|c| c.0).collect();
+            assert_eq!(indices, errors.iter().map(|e| e.0).collect::<Vec<_>>());
+            for (idx, old, new) in correction.corrections {
+                assert_eq!(invalid.chars().nth(idx), Some(old));
+                assert_eq!(valid.chars().nth(idx), Some(new));
+            }
+        }
+
+        // long codex32
+        let long = &checksums["long-codex32"];
+        let valid = long.checksum(&format!("ms10leet{}", "7".repeat(100)));
+        let invalid = add_errors(&valid, &[(9, 1), (50, 2), (100, 3), (120, 4)]);
+        assert_eq!(long.correct(&invalid).unwrap().corrected, valid);
+
+        // Valid strings need no correction
+        let codex32 = &checksums["codex32"];
+        let valid = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+        assert!(codex32.correct(valid).unwrap().corrections.is_empty());
+        // Too many errors
+        let invalid = add_errors(valid, &[(5, 1), (6, 1), (7, 1), (8, 1), (9, 1)]);
+        if let Ok(correction) = codex32.correct(&invalid) {
+            assert_ne!(correction.corrected, valid);
+        }
+        // Errors in the HRP
+        assert!(codex32.correct(&add_errors(valid, &[(0, 1)])).is_err());
+        // Invalid characters
+        assert!(codex32
+            .correct("ms10testsbxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw")
+            .is_err());
+    }
+
+    #[test]
+    fn format_rejections() {
+        let bech32 = &get_checksums()["bech32"];
+        let short = bech32.clone().with_max_data_len(30);
+        let no_hrp = bech32.clone().with_hrp_policy(HrpPolicy::Forbidden);
+        let invalid = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5";
+        let erased = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t?";
+        for checksum in [&short, &no_hrp] {
+            let expected = checksum.check_format(invalid).unwrap_err();
+            assert_eq!(checksum.correct(invalid).unwrap_err(), expected);
+            assert_eq!(
+                checksum.correct_with_erasures(invalid, &[40]).unwrap_err(),
+                expected
+            );
+            assert_eq!(checksum.fill_erasures(erased).unwrap_err(), expected);
+            assert_eq!(checksum.locate_errors(invalid).unwrap_err(), expected);
+            let list = listdecode::list_decode(checksum, invalid, 2, 1000, None);
+            assert_eq!(list.unwrap_err(), expected);
+        }
+        // whereas the unrestricted checksum can correct and fill the string
+        assert!(bech32.correct(invalid).is_ok());
+        assert!(bech32.fill_erasures(erased).is_ok());
+    }
+
+    #[test]
+    fn nonconsecutive_roots() {
+        // The roots of this generator are 1, α and α^5, of which only 1 and α
+        // are used for decoding; the decoder cannot see errors in the third
+        let odd = Checksum::new_in_representation("je2p", "qqp", Isomorphism::identity());
+        let bch = odd.bch_structure().unwrap();
+        assert_eq!(bch.designed_distance, 3);
+        let invalid = "a1qqqpqqqzqqqnt";
+        assert!(!odd.validate_checksum(invalid));
+        let err = odd.correct(invalid).unwrap_err();
+        assert!(err.starts_with("uncorrectable"), "{err}");
+    }
+
+    #[test]
+    fn correct_with_erasures() {
+        let checksums = get_checksums();
+        let codex32 = &checksums["codex32"];
+        let valid = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+
+        // 2 errors plus 4 erasures, given either as markers or as indices
+        let damaged = add_errors(&erase(valid, &[10, 11, 40]), &[(20, 3), (47, 1)]);
+        for correction in [
+            codex32.correct(&damaged).unwrap(),
+            codex32.correct_with_erasures(&damaged, &[30]).unwrap(),
+        ] {
+            assert_eq!(correction.corrected, valid);
+            let corrected: Vec<usize> = correction.corrections.iter().map(|c| c.0).collect();
+            assert_eq!(corrected, [20, 47]);
+            assert_eq!(correction.filled[0], (10, '?', 'x'));
+        }
+        let correction = codex32.correct_with_erasures(&damaged, &[30]).unwrap();
+        let filled: Vec<usize> = correction.filled.iter().map(|c| c.0).collect();
+        assert_eq!(filled, [10, 11, 30, 40]);
+        // The character at index 30 was correct, so is "filled" with itself
+        assert_eq!(correction.filled[2], (30, 'x', 'x'));
+
+        // 8 erasures and no errors
+        let erased = erase(valid, &(20..28).collect::<Vec<_>>());
+        let correction = codex32.correct(&erased).unwrap();
+        assert_eq!(correction.corrected, valid);
+        assert_eq!(correction.filled.len(), 8);
+
+        // 3 errors plus 3 erasures is too many
+        let damaged = add_errors(&erase(valid, &[10, 11, 12]), &[(20, 3), (30, 5), (47, 1)]);
+        assert!(codex32.correct(&damaged).is_err());
+        // Erasures in the HRP or beyond the string are rejected
+        assert!(codex32.correct_with_erasures(valid, &[0]).is_err());
+        assert!(codex32.correct_with_erasures(valid, &[48]).is_err());
+    }
+
+    #[test]
+    fn locate_errors() {
+        let checksums = get_checksums();
+        let bech32 = &checksums["bech32"];
+        let valid = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        assert_eq!(bech32.locate_errors(valid), Ok(vec![]));
+
+        let damaged = add_errors(valid, &[(10, 1), (30, 17)]);
+        assert_eq!(bech32.locate_errors(&damaged), Ok(vec![10, 30]));
+        let upper = damaged.to_uppercase();
+        assert_eq!(bech32.locate_errors(&upper), Ok(vec![10, 30]));
+        // Invalid characters are located directly
+        let invalid = valid.replace("w508", "wb08");
+        assert_eq!(bech32.locate_errors(&invalid), Ok(vec![5]));
+        // Too many errors
+        let damaged = add_errors(valid, &[(10, 1), (20, 2), (30, 17), (35, 3)]);
+        assert!(bech32.locate_errors(&damaged).is_err());
+    }
+
+    /// Replaces characters of a string by the erasure marker `?`
+    fn erase(s: &str, indices: &[usize]) -> String {
+        s.chars()
+            .enumerate()
+            .map(|(i, c)| if indices.contains(&i) { '?' } else { c })
+            .collect()
+    }
+
+    #[test]
+    fn fill_erasures() {
+        let checksums = get_checksums();
+        let codex32 = &checksums["codex32"];
+        let valid = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+
+        // Up to 8 erasures can always be filled, wherever they are
+        for indices in [
+            vec![3],
+            vec![3, 4, 5, 6, 7, 8, 9, 10],
+            vec![40, 41, 42, 43, 44, 45, 46, 47],
+            vec![5, 10, 15, 20, 25, 30, 35, 47],
+        ] {
+            let erased = erase(valid, &indices);
+            let filled = codex32.fill_erasures(&erased).unwrap();
+            assert_eq!(filled.corrected, valid);
+            assert_eq!(filled.filled.len(), indices.len());
+            assert!(filled.corrections.is_empty());
+            for (&idx, (c_idx, old, new)) in indices.iter().zip(filled.filled) {
+                assert_eq!((c_idx, old), (idx, '?'));
+                assert_eq!(valid.chars().nth(idx), Some(new));
+            }
+        }
+        // The full 13 can be filled when the system is solvable, e.g. for the
+        // whole checksum
+        let erased = erase(valid, &(35..48).collect::<Vec<_>>());
+        assert_eq!(codex32.fill_erasures(&erased).unwrap().corrected, valid);
+        let erased = valid.replace("nzvca", "_____");
+        assert_eq!(codex32.fill_erasures(&erased).unwrap().corrected, valid);
+        // Uppercase
+        let upper = "MS12NAMEA320ZYXWVUTSRQPNMLKJHGFEDCAXRPP870HKKQRM";
+        let erased = erase(upper, &[8, 9, 10, 11]);
+        assert_eq!(codex32.fill_erasures(&erased).unwrap().corrected, upper);
+
+        // Too many erasures
+        let erased = erase(valid, &(10..24).collect::<Vec<_>>());
+        assert!(codex32.fill_erasures(&erased).is_err());
+        // Erasures in the HRP
+        assert!(codex32.fill_erasures(&erase(valid, &[1])).is_err());
+        // Other errors
+        let erased = erase(&valid.replace("4nz", "4nq"), &[10]);
+        assert!(codex32.fill_erasures(&erased).is_err());
+
+        // bech32 can fill 6 consecutive erasures
+        let bech32 = &checksums["bech32"];
+        let valid = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        let erased = erase(valid, &[20, 21, 22, 23, 24, 25]);
+        assert_eq!(bech32.fill_erasures(&erased).unwrap().corrected, valid);
+    }
+
+    #[test]
+    fn alternate_representation() {
+        type F = Gf32<0x25>;
+        // Translate the bech32 generator into the other representation
+        let modulus = u5String::from_str("ja45kap").unwrap();
+        let modulus = F::isomorphism().string_from_u5(&modulus).to_string();
+        let foreign = Checksum::new_in_representation(&modulus, "qqqqqp", F::isomorphism());
+        let data = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzz";
+        let summed = foreign.checksum(data);
+        assert!(foreign.validate_checksum(&summed));
+        assert_ne!(summed, get_checksums()["bech32"].checksum(data));
+
+        // Directly compute the checksum using arithmetic in the other field:
+        // (x^n + data) mod generator should equal the target residue
+        let to_poly = |s: &str| {
+            let s = u5String::from_str(s).unwrap();
+            s[..]
+                .iter()
+                .map(|&x| F::from_bits(u8::from(x)))
+                .collect::<Vec<F>>()
+        };
+        let generator = Poly::from_coefficients(to_poly(&modulus));
+        let mut target = to_poly("qqqqqp");
+        target.reverse();
+        let input = u5String::from_hrpstring(&summed).unwrap();
+        let mut data: Vec<F> = input[..].iter().map(|&x| F::from_bits(x.into())).collect();
+        data.reverse();
+        let data = &Poly::from_coefficients(data) + &Poly::monomial(F::ONE, input.len());
+        assert_eq!(data.div_rem(&generator).1, Poly::from_coefficients(target));
+
+        // The code has the same structure as bech32, since it is isomorphic
+        let bch = foreign.bch_structure().unwrap();
+        assert_eq!((bch.designed_distance, bch.length), (4, 1023));
+    }
 
     #[test]
     fn get_mod_string_long_codex32() {
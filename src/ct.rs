@@ -0,0 +1,302 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Constant-Time Arithmetic
+///
+/// codex32 strings carry secret data, so when validating, splitting or
+/// recombining shares we want to avoid branches and memory accesses which
+/// depend on the data. The ordinary `u5` multiplication branches on the bits
+/// of its input, and `u5::from_char` is a big `match`, so this module provides
+/// alternatives, which `Checksum::ct_validate_checksum` uses.
+///
+/// The algorithms are written generically over a `Word` type, so that the
+/// tests can run them on a type which records every operation performed. The
+/// tests then check that this trace is identical for all inputs, i.e. that
+/// the algorithms have no data-dependent control flow. Of course, this checks
+/// the algorithms rather than the machine code that the compiler produces.
+///
+use crate::base32::{u5, CHARSET, REDUCTION};
+use std::ops;
+
+/// The operations used by the constant-time algorithms
+pub trait Word:
+    Copy
+    + ops::BitAnd<Output = Self>
+    + ops::BitOr<Output = Self>
+    + ops::BitXor<Output = Self>
+    + ops::Shl<u32, Output = Self>
+    + ops::Shr<u32, Output = Self>
+{
+    /// Constructs a word from a public constant
+    fn constant(x: u32) -> Self;
+    /// Two's complement negation
+    fn wrapping_neg(self) -> Self;
+}
+
+impl Word for u32 {
+    fn constant(x: u32) -> u32 {
+        x
+    }
+
+    fn wrapping_neg(self) -> u32 {
+        u32::wrapping_neg(self)
+    }
+}
+
+/// Expands the low bit of a word into a mask which is all 0s or all 1s
+fn bit_mask<W: Word>(x: W) -> W {
+    (x & W::constant(1)).wrapping_neg()
+}
+
+/// Returns an all-1s mask if `x` is zero, and an all-0s mask otherwise
+pub fn zero_mask<W: Word>(x: W) -> W {
+    // The top bit of x | -x is set iff x is nonzero
+    let nonzero = (x | x.wrapping_neg()) >> 31;
+    bit_mask(nonzero ^ W::constant(1))
+}
+
+/// Multiplies two GF(32) elements, given as 5-bit encodings
+pub fn mul<W: Word>(a: W, mut b: W) -> W {
+    let mut res = W::constant(0);
+    for i in 0..5 {
+        res = res ^ (b & bit_mask(a >> i));
+        // Multiply b by alpha, reducing by x^5 + x^3 + 1 if the top bit was set
+        b = (b << 1) ^ (bit_mask(b >> 4) & W::constant(REDUCTION.into()));
+    }
+    res
+}
+
+/// Decodes a character (given as its Unicode scalar value) into its 5-bit
+/// encoding, returning the encoding and a mask which is all 1s iff the
+/// character was valid
+pub fn from_char<W: Word>(c: W) -> (W, W) {
+    let mut value = W::constant(0);
+    let mut found = W::constant(0);
+    for (i, &lower) in CHARSET.iter().enumerate() {
+        let upper = lower.to_ascii_uppercase();
+        let eq_lower = zero_mask(c ^ W::constant(lower.into()));
+        // For digits, the "uppercase" character is the same as the lowercase
+        // one; comparing against it anyway keeps the trace uniform.
+        let eq_upper = zero_mask(c ^ W::constant(upper.into()));
+        let eq = eq_lower | eq_upper;
+        value = value | (W::constant(i as u32) & eq);
+        found = found | eq;
+    }
+    (value, found)
+}
+
+/// Returns an all-1s mask if every word in the slice is zero, and an all-0s
+/// mask otherwise
+pub fn all_zero<W: Word>(xs: &[W]) -> W {
+    let acc = xs.iter().fold(W::constant(0), |acc, &x| acc | x);
+    zero_mask(acc)
+}
+
+/// Applies a public GF(2)-linear map, such as a change of field representation,
+/// to a 5-bit encoding
+///
+/// The map is given by its images of the encodings 1, 2, 4, 8 and 16.
+pub fn linear_map<W: Word>(basis_images: &[u5; 5], x: W) -> W {
+    let mut res = W::constant(0);
+    for (i, &image) in basis_images.iter().enumerate() {
+        res = res ^ (bit_mask(x >> i as u32) & W::constant(u8::from(image).into()));
+    }
+    res
+}
+
+/// Computes the remainder of a polynomial modulo a public generator, and adds
+/// a public target residue to it, as `Checksum::polymod` does
+///
+/// The generator is given little-endian without its leading 1, while the
+/// input and target, like the returned residue, are big-endian.
+pub fn polymod<W: Word>(generator: &[u5], target: &[u5], input: &[W]) -> Vec<W> {
+    let n = target.len();
+    let mut ret = vec![W::constant(0); n];
+    ret[n - 1] = W::constant(1); // start with the polynomial 1
+    for &ch in input {
+        let xn = ret[0];
+        ret.copy_within(1.., 0);
+        ret[n - 1] = ch;
+        for (i, &g) in generator.iter().rev().enumerate() {
+            ret[i] = ret[i] ^ mul(W::constant(u8::from(g).into()), xn);
+        }
+    }
+    for (r, &t) in ret.iter_mut().zip(target) {
+        *r = *r ^ W::constant(u8::from(t).into());
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static TRACE: RefCell<Vec<&'static str>> = const { RefCell::new(vec![]) };
+    }
+
+    /// A word which records every operation performed on it
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    struct Traced(u32);
+
+    fn record(op: &'static str) {
+        TRACE.with(|t| t.borrow_mut().push(op));
+    }
+
+    /// Runs a closure, returning its result and the trace of operations
+    fn trace<T>(f: impl FnOnce() -> T) -> (T, Vec<&'static str>) {
+        TRACE.with(|t| t.borrow_mut().clear());
+        let ret = f();
+        (ret, TRACE.with(|t| t.replace(vec![])))
+    }
+
+    impl Word for Traced {
+        fn constant(x: u32) -> Traced {
+            record("const");
+            Traced(x)
+        }
+        fn wrapping_neg(self) -> Traced {
+            record("neg");
+            Traced(self.0.wrapping_neg())
+        }
+    }
+    impl ops::BitAnd for Traced {
+        type Output = Traced;
+        fn bitand(self, other: Traced) -> Traced {
+            record("and");
+            Traced(self.0 & other.0)
+        }
+    }
+    impl ops::BitOr for Traced {
+        type Output = Traced;
+        fn bitor(self, other: Traced) -> Traced {
+            record("or");
+            Traced(self.0 | other.0)
+        }
+    }
+    impl ops::BitXor for Traced {
+        type Output = Traced;
+        fn bitxor(self, other: Traced) -> Traced {
+            record("xor");
+            Traced(self.0 ^ other.0)
+        }
+    }
+    impl ops::Shl<u32> for Traced {
+        type Output = Traced;
+        fn shl(self, n: u32) -> Traced {
+            record("shl");
+            Traced(self.0 << n)
+        }
+    }
+    impl ops::Shr<u32> for Traced {
+        type Output = Traced;
+        fn shr(self, n: u32) -> Traced {
+            record("shr");
+            Traced(self.0 >> n)
+        }
+    }
+
+    #[test]
+    fn mul_is_constant_time() {
+        use crate::base32::u5;
+        let (_, reference) = trace(|| mul(Traced(0), Traced(0)));
+        for a in 0..32 {
+            for b in 0..32 {
+                let (res, ops) = trace(|| mul(Traced(a), Traced(b)));
+                assert_eq!(ops, reference, "trace differs for {a} * {b}");
+                let expected = u5::from(a as u8) * u5::from(b as u8);
+                assert_eq!(res.0, u32::from(u8::from(expected)));
+            }
+        }
+    }
+
+    #[test]
+    fn from_char_is_constant_time() {
+        use crate::base32::u5;
+        let (_, reference) = trace(|| from_char(Traced(0)));
+        let chars = (0..0x80u8)
+            .map(char::from)
+            .chain(['ß', 'ä', '€', '\u{10ffff}']);
+        for c in chars {
+            let ((value, found), ops) = trace(|| from_char(Traced(c.into())));
+            assert_eq!(ops, reference, "trace differs for {c:?}");
+            match u5::from_char(c) {
+                Ok(x) => {
+                    assert_eq!(found.0, u32::MAX);
+                    assert_eq!(value.0, u32::from(u8::from(x)));
+                }
+                Err(_) => assert_eq!(found.0, 0),
+            }
+        }
+    }
+
+    #[test]
+    fn all_zero_is_constant_time() {
+        let inputs = [[0, 0, 0, 0], [1, 0, 0, 0], [0, 0, 0, 31], [5, 6, 7, 8]];
+        let (_, reference) = trace(|| all_zero(&[Traced(0); 4]));
+        for input in inputs {
+            let words = input.map(Traced);
+            let (res, ops) = trace(|| all_zero(&words));
+            assert_eq!(ops, reference, "trace differs for {input:?}");
+            assert_eq!(res.0 == u32::MAX, input == [0; 4]);
+        }
+    }
+
+    #[test]
+    fn validate_is_constant_time() {
+        use crate::checksum32::{Checksum, Codex32, StaticChecksum};
+        use crate::gf32::Isomorphism;
+
+        let words = |data: &str| data.chars().map(|c| Traced(c.into())).collect::<Vec<_>>();
+        let codex32 = Codex32::to_checksum();
+        let valid = "0testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+        let (_, reference) = trace(|| codex32.ct_validate_words("ms", &words(valid)));
+        let inputs = [
+            valid,
+            "0TESTSXXXXXXXXXXXXXXXXXXXXXXXXXX4NZVCA9CMCZLW",
+            "0testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlx",
+            "0testsbxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw",
+            "qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq",
+        ];
+        for data in inputs {
+            let (res, ops) = trace(|| codex32.ct_validate_words("ms", &words(data)));
+            assert_eq!(ops, reference, "trace differs for {data}");
+            let s = format!("ms1{data}");
+            assert_eq!(res.0 == u32::MAX, codex32.validate_checksum(&s));
+            assert_eq!(
+                codex32.ct_validate_checksum(&s),
+                codex32.validate_checksum(&s)
+            );
+        }
+
+        // Alternate representations
+        let alt = Checksum::new_in_representation(
+            "ja45kap",
+            "qqqqqq",
+            Isomorphism::from_reduction(0x25).unwrap(),
+        );
+        let summed = alt.checksum("1qpzry9x8");
+        let changed = summed.replacen('z', "y", 1);
+        let (_, reference) = trace(|| alt.ct_validate_words("", &words(&summed[1..])));
+        for s in [&summed, &changed] {
+            let (res, ops) = trace(|| alt.ct_validate_words("", &words(&s[1..])));
+            assert_eq!(ops, reference, "trace differs for {s}");
+            assert_eq!(res.0 == u32::MAX, alt.validate_checksum(s));
+            assert_eq!(alt.ct_validate_checksum(s), alt.validate_checksum(s));
+        }
+        assert!(alt.ct_validate_checksum(&summed));
+        assert!(!alt.ct_validate_checksum(&changed));
+    }
+}
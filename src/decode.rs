@@ -0,0 +1,289 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// BCH Decoding
+///
+/// Syndrome decoding for our checksums, which are all BCH codes (see the `bch`
+/// module). Given the syndromes S_i = Σ e_j (rβ^i)^j, for i from 0 to δ-2,
+/// where the e_j are the errors at positions j (counting from 0 at the end of
+/// the string), we write X_k = β^j and Y_k = e_j r^j for each error so that
+/// S_i = Σ Y_k X_k^i. Then
+///
+///   1. the Berlekamp-Massey algorithm finds the error locator polynomial
+///      Λ(z) = Π (1 - X_k z);
+///   2. a Chien search finds its roots X_k^{-1}, and therefore the positions j;
+///   3. Forney's formula Y_k = X_k Ω(X_k^{-1}) / Λ'(X_k^{-1}), where Ω(z) is
+///      S(z)Λ(z) mod z^{δ-1}, gives the error values.
+///
+use crate::base32::u5;
+use crate::bch::BchStructure;
+use crate::extfield::ExtElem;
+use crate::field::Field;
+use crate::poly::Poly;
+
+/// Runs the Berlekamp-Massey algorithm on a sequence of syndromes, returning
+/// the shortest linear feedback shift register which generates them, as the
+/// connection polynomial C(z) and its length L
+pub fn berlekamp_massey<F: Field>(syndromes: &[F]) -> (Poly<F>, usize) {
+    let mut conn = Poly::one();
+    let mut prev = Poly::one();
+    let mut len = 0;
+    let mut shift = 1;
+    let mut prev_disc = F::ONE;
+    for n in 0..syndromes.len() {
+        // Compute the discrepancy between the next syndrome and the prediction
+        let mut disc = syndromes[n];
+        for i in 1..=len {
+            disc += conn.coefficient(i) * syndromes[n - i];
+        }
+        if disc == F::ZERO {
+            shift += 1;
+            continue;
+        }
+        let adjust = Poly::monomial(disc / prev_disc, shift);
+        let next = &conn + &(&adjust * &prev);
+        if 2 * len <= n {
+            prev = std::mem::replace(&mut conn, next);
+            len = n + 1 - len;
+            prev_disc = disc;
+            shift = 1;
+        } else {
+            conn = next;
+            shift += 1;
+        }
+    }
+    (conn, len)
+}
+
+/// Decodes a set of syndromes for a string of `len` characters, returning the
+/// position (counting from 0 at the end of the string) and value of each error
+///
+/// The syndromes are those returned by `Checksum::syndromes`. The error values
+/// are the values that were added to the correct characters, so that adding
+/// them again will recover the correct string.
+pub fn decode(
+    bch: &BchStructure,
+    syndromes: &[ExtElem],
+    len: usize,
+) -> Result<Vec<(usize, u5)>, String> {
+    decode_with_erasures(bch, syndromes, len, &[])
+}
+
+/// Decodes a set of syndromes for a string of `len` characters, some of whose
+/// positions (counting from 0 at the end of the string) are known to be
+/// erased, i.e. to have unknown values
+///
+/// With ρ erasures and ν further errors, decoding succeeds as long as
+/// 2ν + ρ is less than the designed distance. We multiply the syndrome
+/// polynomial by the erasure locator Γ(z) = Π (1 - β^j z) over the erased
+/// positions j, and run Berlekamp-Massey on the upper coefficients of the
+/// result (the "Forney syndromes"), which depend only on the unknown errors.
+/// The full locator is then the product of the two.
+///
+/// The returned list contains every erased position, whose value may be zero
+/// if the erased character happened to be correct, followed by the positions
+/// and (nonzero) values of any other errors.
+pub fn decode_with_erasures(
+    bch: &BchStructure,
+    syndromes: &[ExtElem],
+    len: usize,
+    erasures: &[usize],
+) -> Result<Vec<(usize, u5)>, String> {
+    if syndromes.iter().all(|&s| s == ExtElem::ZERO) {
+        return Ok(erasures.iter().map(|&j| (j, u5::ZERO)).collect());
+    }
+    if len as u64 > bch.length {
+        return Err(format!(
+            "string of length {len} is longer than the code length {}",
+            bch.length
+        ));
+    }
+    if let Some(j) = erasures.iter().find(|&&j| j >= len) {
+        return Err(format!("erasure at position {j} is beyond the string"));
+    }
+    let n_syndromes = syndromes.len();
+    if erasures.len() > n_syndromes {
+        return Err(format!("too many erasures (more than {n_syndromes})"));
+    }
+
+    let mut erasure_locator = Poly::one();
+    for &j in erasures {
+        let factor = Poly::from_coefficients(vec![ExtElem::ONE, bch.beta.pow(j)]);
+        erasure_locator *= &factor;
+    }
+    let mut forney_syn = Poly::from_coefficients(syndromes.to_vec());
+    forney_syn *= &erasure_locator;
+    let forney_syn: Vec<ExtElem> = (erasures.len()..n_syndromes)
+        .map(|i| forney_syn.coefficient(i))
+        .collect();
+
+    let (error_locator, n_errors) = berlekamp_massey(&forney_syn);
+    if 2 * n_errors + erasures.len() > n_syndromes || error_locator.degree() != Some(n_errors) {
+        return Err(format!(
+            "too many errors (more than {} with {} erasures)",
+            (n_syndromes - erasures.len()) / 2,
+            erasures.len(),
+        ));
+    }
+    let locator = &error_locator * &erasure_locator;
+    let n_roots = n_errors + erasures.len();
+
+    // Chien search: look for positions j such that β^{-j} is a root
+    let beta_inv = bch.beta.inverse().unwrap();
+    let mut x_inv = ExtElem::ONE;
+    let mut positions = vec![];
+    for j in 0..len {
+        if locator.evaluate(x_inv) == ExtElem::ZERO {
+            positions.push((j, x_inv));
+        }
+        x_inv *= beta_inv;
+    }
+    if positions.len() != n_roots {
+        return Err(format!(
+            "error locator has {} roots within the string, but degree {n_roots}",
+            positions.len()
+        ));
+    }
+
+    // Forney's formula
+    let mut syn_poly = Poly::from_coefficients(syndromes.to_vec());
+    syn_poly *= &locator;
+    let evaluator = Poly::from_coefficients(
+        syn_poly.coefficients()[..n_syndromes.min(syn_poly.coefficients().len())].to_vec(),
+    );
+    let deriv = locator.derivative();
+    let mut ret = Vec::with_capacity(n_roots);
+    for (j, x_inv) in positions {
+        let y = evaluator.evaluate(x_inv) / (x_inv * deriv.evaluate(x_inv));
+        let value = y / bch.first_root.pow(j);
+        match value.to_u5() {
+            Some(v) if erasures.contains(&j) || v != u5::ZERO => ret.push((j, v)),
+            _ => return Err(format!("error at position {j} has invalid value {value}")),
+        }
+    }
+    ret.sort_by_key(|&(j, _)| !erasures.contains(&j));
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gf1024::Fe1024;
+
+    #[test]
+    fn berlekamp_massey_lfsr() {
+        // The Fibonacci-like sequence s_n = s_{n-1} + ζ s_{n-2}
+        let zeta = Fe1024::ZETA;
+        let mut seq = vec![Fe1024::ONE, zeta];
+        for n in 2..10 {
+            let next = seq[n - 1] + zeta * seq[n - 2];
+            seq.push(next);
+        }
+        let (conn, len) = berlekamp_massey(&seq);
+        assert_eq!(len, 2);
+        assert_eq!(
+            conn,
+            Poly::from_coefficients(vec![Fe1024::ONE, Fe1024::ONE, zeta])
+        );
+
+        // The zero sequence needs no register at all
+        let (conn, len) = berlekamp_massey(&[Fe1024::ZERO; 6]);
+        assert_eq!((conn, len), (Poly::one(), 0));
+    }
+
+    #[test]
+    fn decode_synthetic_errors() {
+        let modulus = crate::checksum32::get_checksums()["codex32"].generator();
+        let bch = BchStructure::analyze(&modulus).unwrap();
+        let errors = [(3, u5::from(7)), (17, u5::from(1)), (40, u5::from(31))];
+        let syndromes: Vec<ExtElem> = bch
+            .consecutive_roots()
+            .into_iter()
+            .map(|root| {
+                errors.iter().fold(ExtElem::ZERO, |acc, &(j, e)| {
+                    acc + ExtElem::from_u5(e) * root.pow(j)
+                })
+            })
+            .collect();
+        let mut decoded = decode(&bch, &syndromes, 48).unwrap();
+        decoded.sort_by_key(|&(j, _)| j);
+        assert_eq!(decoded, errors);
+
+        // Errors beyond the end of the string cannot be found
+        assert!(decode(&bch, &syndromes, 30).is_err());
+        // Strings longer than the code length cannot be decoded
+        assert!(decode(&bch, &syndromes, 94).is_err());
+    }
+
+    #[test]
+    fn decode_errors_and_erasures() {
+        let modulus = crate::checksum32::get_checksums()["codex32"].generator();
+        let bch = BchStructure::analyze(&modulus).unwrap();
+        let syndromes_of = |errors: &[(usize, u5)]| -> Vec<ExtElem> {
+            bch.consecutive_roots()
+                .into_iter()
+                .map(|root| {
+                    errors.iter().fold(ExtElem::ZERO, |acc, &(j, e)| {
+                        acc + ExtElem::from_u5(e) * root.pow(j)
+                    })
+                })
+                .collect()
+        };
+
+        // 2 errors and 4 erasures, one of which is actually correct
+        let errors = [(5, u5::from(3)), (30, u5::from(9))];
+        let erased = [
+            (0, u5::from(1)),
+            (12, u5::from(0)),
+            (13, u5::from(20)),
+            (47, u5::from(2)),
+        ];
+        let all: Vec<_> = errors.iter().chain(erased.iter()).copied().collect();
+        let syndromes = syndromes_of(&all);
+        let erasures: Vec<usize> = erased.iter().map(|&(j, _)| j).collect();
+        let decoded = decode_with_erasures(&bch, &syndromes, 48, &erasures).unwrap();
+        let mut filled = decoded[..erasures.len()].to_vec();
+        let mut corrected = decoded[erasures.len()..].to_vec();
+        filled.sort_by_key(|&(j, _)| j);
+        corrected.sort_by_key(|&(j, _)| j);
+        assert_eq!(filled, erased);
+        assert_eq!(corrected, errors);
+
+        // 8 erasures and no errors
+        let erased: Vec<_> = (20..28).map(|j| (j, u5::from(j as u8))).collect();
+        let erasures: Vec<usize> = erased.iter().map(|&(j, _)| j).collect();
+        let mut decoded =
+            decode_with_erasures(&bch, &syndromes_of(&erased), 48, &erasures).unwrap();
+        decoded.sort_by_key(|&(j, _)| j);
+        assert_eq!(decoded, erased);
+
+        // 3 errors and 3 erasures is too many
+        let errors = [(1, u5::from(3)), (2, u5::from(9)), (3, u5::from(1))];
+        let syndromes = syndromes_of(&errors);
+        assert!(decode_with_erasures(&bch, &syndromes, 48, &[10, 11, 12]).is_err());
+        // but fine with 2 erasures
+        let mut decoded = decode_with_erasures(&bch, &syndromes, 48, &[10, 11]).unwrap();
+        decoded.sort_by_key(|&(j, _)| j);
+        assert_eq!(
+            decoded,
+            [
+                (1, u5::from(3)),
+                (2, u5::from(9)),
+                (3, u5::from(1)),
+                (10, u5::ZERO),
+                (11, u5::ZERO)
+            ]
+        );
+    }
+}
@@ -0,0 +1,351 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Checksum Definitions
+///
+/// Rather than editing `get_checksums` and recompiling, extra checksums can be
+/// loaded from a definitions file, which is merged with the built-in ones. The
+/// file is made up of sections, one per checksum, like
+///
+/// ```text
+/// # Comments start with '#'
+/// [my-checksum]
+/// modulus = ja45kap          # or gen = [18,29,21,20,22,29,1]
+/// residue = qqqqqp           # or target = qqqqqp
+/// max_length = 90            # optional, in characters after the separator
+/// hrp = required             # optional; one of required, optional or none
+/// ```
+///
+/// The `gen=` form is the list printed by gen_bech.py, which must include the
+/// final 1 (see the `checksum32` module documentation). Every problem with a
+/// file is reported, along with its line number.
+///
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::base32::u5String;
+use crate::checksum32::{get_checksums, Checksum, HrpPolicy};
+use crate::gf32::Isomorphism;
+
+/// The fields of a single section of a definitions file
+#[derive(Default)]
+struct Section {
+    name: String,
+    line: usize,
+    fields: HashMap<String, (usize, String)>,
+}
+
+impl Section {
+    /// Converts the section into a checksum, pushing any errors onto `errors`
+    fn finish(&self, errors: &mut Vec<String>) -> Option<Checksum> {
+        let mut modulus = match (self.fields.get("modulus"), self.fields.get("gen")) {
+            (Some((_, modulus)), None) => modulus.clone(),
+            (None, Some((line, gen))) => match parse_gen(gen) {
+                Ok(modulus) => modulus,
+                Err(e) => {
+                    errors.push(format!("line {line}: {e}"));
+                    return None;
+                }
+            },
+            (Some(_), Some(_)) => {
+                errors.push(format!(
+                    "line {}: checksum {} has both modulus and gen",
+                    self.line, self.name
+                ));
+                return None;
+            }
+            (None, None) => {
+                errors.push(format!(
+                    "line {}: checksum {} has no modulus or gen",
+                    self.line, self.name
+                ));
+                return None;
+            }
+        };
+        modulus.make_ascii_lowercase();
+        let residue = match (self.fields.get("residue"), self.fields.get("target")) {
+            (Some((_, residue)), None) | (None, Some((_, residue))) => residue.to_lowercase(),
+            (Some(_), Some(_)) => {
+                errors.push(format!(
+                    "line {}: checksum {} has both residue and target",
+                    self.line, self.name
+                ));
+                return None;
+            }
+            (None, None) => {
+                errors.push(format!(
+                    "line {}: checksum {} has no residue",
+                    self.line, self.name
+                ));
+                return None;
+            }
+        };
+
+        let mut checksum = match Checksum::try_new_in_representation(
+            &modulus,
+            &residue,
+            Isomorphism::identity(),
+        ) {
+            Ok(checksum) => checksum,
+            Err(e) => {
+                errors.push(format!("line {}: {e}", self.line));
+                return None;
+            }
+        };
+        if checksum.generator().coefficient(0) == crate::base32::u5::ZERO {
+            errors.push(format!(
+                "line {}: generator of checksum {} is divisible by x",
+                self.line, self.name
+            ));
+            return None;
+        }
+        let n_errors = errors.len();
+        if let Some((line, max)) = self.fields.get("max_length") {
+            match max.parse::<usize>() {
+                Ok(max) if max > checksum.checksum_len() => {
+                    checksum = checksum.with_max_data_len(max);
+                }
+                Ok(_) => errors.push(format!(
+                    "line {line}: max_length must exceed the checksum length {}",
+                    checksum.checksum_len()
+                )),
+                Err(e) => errors.push(format!("line {line}: invalid max_length \"{max}\": {e}")),
+            }
+        }
+        if let Some((line, policy)) = self.fields.get("hrp") {
+            match &policy[..] {
+                "required" => checksum = checksum.with_hrp_policy(HrpPolicy::Required),
+                "optional" => checksum = checksum.with_hrp_policy(HrpPolicy::Optional),
+                "none" => checksum = checksum.with_hrp_policy(HrpPolicy::Forbidden),
+                _ => errors.push(format!(
+                    "line {line}: hrp must be required, optional or none, not \"{policy}\""
+                )),
+            }
+        }
+        if errors.len() == n_errors {
+            Some(checksum)
+        } else {
+            None
+        }
+    }
+}
+
+/// Converts a `gen=` list of the generator's coefficients, which may be in
+/// brackets, into a modulus string
+fn parse_gen(gen: &str) -> Result<String, String> {
+    let gen = gen.trim();
+    let gen = gen
+        .strip_prefix('[')
+        .and_then(|gen| gen.strip_suffix(']'))
+        .unwrap_or(gen);
+    let coeffs = gen
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|coeff| !coeff.is_empty())
+        .map(|coeff| match coeff.parse::<u8>() {
+            Ok(n) if n < 32 => Ok(n),
+            _ => Err(format!("invalid gen coefficient \"{coeff}\"")),
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    if coeffs.last() != Some(&1) {
+        return Err("gen list should end in 1".into());
+    }
+    Ok(u5String::from(coeffs).to_string())
+}
+
+/// Parses the contents of a definitions file, returning the named checksums
+/// in the order they appear
+///
+/// Names must be unique, both within the file and with respect to the
+/// built-in checksums. If there are any errors, all of them are returned,
+/// one per line.
+pub fn parse_definitions(text: &str) -> Result<Vec<(String, Checksum)>, String> {
+    let builtins = get_checksums();
+    let mut errors = vec![];
+    let mut sections: Vec<Section> = vec![];
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = match line.split_once('#') {
+            Some((content, _)) => content,
+            None => line,
+        }
+        .trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = match name.strip_suffix(']') {
+                Some(name) => name.trim(),
+                None => {
+                    errors.push(format!("line {line_no}: unterminated section header"));
+                    continue;
+                }
+            };
+            if name.is_empty()
+                || name.contains(char::is_whitespace)
+                || name == "all"
+                || name == "auto"
+            {
+                errors.push(format!("line {line_no}: invalid checksum name \"{name}\""));
+            } else if builtins.contains_key(name) {
+                errors.push(format!(
+                    "line {line_no}: checksum {name} is already built in"
+                ));
+            } else if let Some(prev) = sections.iter().find(|sec| sec.name == name) {
+                errors.push(format!(
+                    "line {line_no}: checksum {name} was already defined on line {}",
+                    prev.line
+                ));
+            }
+            sections.push(Section {
+                name: name.into(),
+                line: line_no,
+                ..Default::default()
+            });
+        } else if let Some((key, value)) = line.split_once('=') {
+            let (key, value) = (key.trim(), value.trim());
+            let section = match sections.last_mut() {
+                Some(section) => section,
+                None => {
+                    errors.push(format!("line {line_no}: {key} is outside of any section"));
+                    continue;
+                }
+            };
+            if !["modulus", "gen", "residue", "target", "max_length", "hrp"].contains(&key) {
+                errors.push(format!("line {line_no}: unknown key \"{key}\""));
+            } else if let Some((prev, _)) = section.fields.get(key) {
+                errors.push(format!(
+                    "line {line_no}: {key} was already given on line {prev}"
+                ));
+            } else {
+                section.fields.insert(key.into(), (line_no, value.into()));
+            }
+        } else {
+            errors.push(format!("line {line_no}: expected [name] or key = value"));
+        }
+    }
+
+    let checksums: Vec<_> = sections
+        .iter()
+        .filter_map(|sec| sec.finish(&mut errors).map(|cs| (sec.name.clone(), cs)))
+        .collect();
+    if errors.is_empty() {
+        Ok(checksums)
+    } else {
+        errors.sort_by_key(|e| {
+            e.strip_prefix("line ")
+                .and_then(|e| e.split(':').next())
+                .and_then(|n| n.parse::<usize>().ok())
+        });
+        Err(errors.join("\n"))
+    }
+}
+
+/// Loads a definitions file, returning the built-in checksums along with
+/// those defined in the file
+pub fn load_checksums(path: &Path) -> Result<HashMap<String, Checksum>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("could not read definitions file {}: {e}", path.display()))?;
+    let mut checksums = get_checksums();
+    checksums.extend(parse_definitions(&text)?);
+    Ok(checksums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_valid() {
+        let text = "
+            # bech32 again, under other names
+            [bech32-copy]
+            modulus = ja45kap
+            residue = qqqqqp   # the target
+
+            [bech32-gen]
+            gen = [18, 29, 21, 20, 22, 29, 1]
+            target = QQQQQP
+            max_length = 40
+            hrp = required
+
+            [no-hrp]
+            gen = 18,29,21,20,22,29,1
+            residue = qqqqqp
+            hrp = none
+        ";
+        let defs = parse_definitions(text).unwrap();
+        let names: Vec<_> = defs.iter().map(|(name, _)| &name[..]).collect();
+        assert_eq!(names, ["bech32-copy", "bech32-gen", "no-hrp"]);
+
+        let bech32 = &get_checksums()["bech32"];
+        // (built-in checksums are limited to their code length)
+        assert_eq!(&defs[0].1.clone().with_max_code_len(1023), bech32);
+        assert_eq!(defs[1].1.generator(), bech32.generator());
+        assert_eq!(defs[1].1.max_data_len(), Some(40));
+        assert_eq!(defs[1].1.hrp_policy(), HrpPolicy::Required);
+
+        let valid = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        assert!(defs[0].1.validate_checksum(valid));
+        assert!(defs[1].1.validate_checksum(valid));
+        assert!(defs[1]
+            .1
+            .try_checksum("bc1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq")
+            .is_err());
+        assert!(defs[1].1.try_checksum("qw508d6q").is_err());
+        assert!(defs[2].1.validate_checksum(&defs[2].1.checksum("qw508d6q")));
+        assert!(!defs[2].1.validate_checksum(valid));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let text = "
+            modulus = ja45kap
+            [bech32]
+            modulus = ja45kap
+            residue = qqqqqp
+            [ok]
+            modulus = ja45kap
+            residue = qqqqqp
+            [ok]
+            gen = [18, 29, 21, 20, 22, 29, 2]
+            residue = qqqqqp
+            [short]
+            modulus = ja45kap
+            residue = qqqqq
+            [bad-values]
+            modulus = ja45kap
+            modulus = ja45kap
+            residue = qqqqqp
+            max_length = 6
+            hrp = sometimes
+            colour = blue
+            this line is nonsense
+        ";
+        let errors = parse_definitions(text).unwrap_err();
+        let lines: Vec<_> = errors.lines().collect();
+        assert_eq!(lines.len(), 10, "{errors}");
+        assert!(lines[0].starts_with("line 2: modulus is outside"));
+        assert!(lines[1].starts_with("line 3: checksum bech32 is already built in"));
+        assert!(lines[2].starts_with("line 9: checksum ok was already defined on line 6"));
+        assert!(lines[3].starts_with("line 10: gen list should end in 1"));
+        assert!(lines[4].starts_with("line 12: Residue string"));
+        assert!(lines[5].starts_with("line 17: modulus was already given on line 16"));
+        assert!(lines[6].starts_with("line 19: max_length must exceed"));
+        assert!(lines[7].starts_with("line 20: hrp must be"));
+        assert!(lines[8].starts_with("line 21: unknown key"));
+        assert!(lines[9].starts_with("line 22: expected"));
+    }
+}
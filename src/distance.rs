@@ -0,0 +1,599 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Minimum Distance
+///
+/// The BCH bound (see the `bch` module) is only a lower bound on the minimum
+/// distance of a code. Here we compute the true minimum distance of a checksum
+/// when used on strings of a particular length, by finding a lowest-weight
+/// codeword: a nonzero polynomial of degree less than the length which is
+/// divisible by the generator. Equivalently, a set of columns of the
+/// parity-check matrix, whose jth column is x^j mod g, which is linearly
+/// dependent.
+///
+/// Two searches are combined:
+///
+///   1. An information-set search (Lee-Brickell), which repeatedly picks a
+///      random set of checksum-length many positions, puts the parity-check
+///      matrix in systematic form on them, and tries every codeword which is
+///      nonzero in at most two of the other positions. This quickly finds
+///      low-weight codewords when they are plentiful, giving an upper bound.
+///   2. An exhaustive meet-in-the-middle search, which for each weight w
+///      splits a candidate codeword into its first a and last w - a positions,
+///      tabulates the syndromes of all possible last parts and looks up the
+///      syndromes of all first parts. This either finds a codeword of weight w
+///      or proves that there are none, raising the lower bound.
+///
+/// Both are spread across all available threads. If the exhaustive search
+/// would exceed its work limit before the bounds meet, only bounds are
+/// reported.
+///
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::base32::{u5, u5String};
+use crate::checksum32::Checksum;
+use crate::packed::MAX_CHECKSUM_LEN;
+use crate::poly::Poly;
+
+/// The maximum number of entries in the meet-in-the-middle lookup table
+const TABLE_LIMIT: u64 = 1 << 22;
+
+/// Mask with the lowest bit of each packed 5-bit coefficient set
+const LOW_BITS: u128 = {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < MAX_CHECKSUM_LEN {
+        mask |= 1 << (5 * i);
+        i += 1;
+    }
+    mask
+};
+
+/// Counts the nonzero coefficients of a packed vector
+fn packed_weight(x: u128) -> usize {
+    ((x | x >> 1 | x >> 2 | x >> 3 | x >> 4) & LOW_BITS).count_ones() as usize
+}
+
+/// Extracts the `i`th coefficient of a packed vector
+fn packed_coeff(x: u128, i: usize) -> u5 {
+    u5::from(((x >> (5 * i)) & 0x1f) as u8)
+}
+
+/// Multiplies every coefficient of a packed vector of length `len` by `v`
+fn packed_scale(x: u128, v: u5, len: usize) -> u128 {
+    (0..len).fold(0, |acc, i| {
+        acc | u128::from(u8::from(packed_coeff(x, i) * v)) << (5 * i)
+    })
+}
+
+/// The result of a minimum distance computation
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MinimumDistance {
+    /// The string length, in characters (including any expanded HRP)
+    pub length: usize,
+    /// A proven lower bound on the minimum distance
+    pub lower_bound: usize,
+    /// The weight of the lowest-weight codeword found, if any
+    pub upper_bound: Option<usize>,
+    /// A codeword of weight `upper_bound`, as a big-endian string of `length`
+    /// characters whose residue (with zero target residue) is zero
+    pub codeword: Option<u5String>,
+}
+
+impl MinimumDistance {
+    /// Whether the bounds meet, i.e. the minimum distance is known exactly
+    pub fn is_exact(&self) -> bool {
+        self.upper_bound == Some(self.lower_bound)
+    }
+}
+
+impl fmt::Display for MinimumDistance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.upper_bound {
+            Some(upper) if upper == self.lower_bound => {
+                write!(f, "length {}: distance {upper}", self.length)?
+            }
+            Some(upper) => write!(
+                f,
+                "length {}: distance between {} and {upper}",
+                self.length, self.lower_bound
+            )?,
+            None => write!(
+                f,
+                "length {}: distance at least {}",
+                self.length, self.lower_bound
+            )?,
+        }
+        if let Some(ref codeword) = self.codeword {
+            write!(f, "\nexample codeword: {codeword}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of threads to spread searches across
+pub(crate) fn n_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// A xorshift64 random number generator, seeded deterministically so that
+/// searches, benchmarks and tests are reproducible
+///
+/// We do not need good randomness, only a lot of it.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    /// The default seed
+    pub(crate) const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+    /// Creates a generator with the given (nonzero) seed
+    pub(crate) fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    /// Creates the generator for the `t`th thread
+    pub(crate) fn for_thread(t: usize) -> Rng {
+        Rng(Self::SEED ^ (t as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    /// Returns a pseudorandom number less than `n`
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    /// Returns a pseudorandom GF(32) element
+    pub(crate) fn u5(&mut self) -> u5 {
+        u5::from(self.below(32) as u8)
+    }
+
+    /// Returns a string of `len` pseudorandom GF(32) elements
+    pub(crate) fn u5_string(&mut self, len: usize) -> u5String {
+        (0..len).map(|_| self.u5()).collect::<Vec<u5>>().into()
+    }
+}
+
+/// A callback for `ParityCheck::for_each_combination`
+pub(crate) type CombinationFn<'a> = dyn FnMut(u128, &[(usize, u5)]) -> bool + 'a;
+
+/// The parity-check matrix of a checksum at a given length, with the columns
+/// x^j mod g packed into `u128`s along with all their nonzero multiples
+pub(crate) struct ParityCheck {
+    pub(crate) checksum_len: usize,
+    /// `scaled[j][v]` is v times the jth column
+    pub(crate) scaled: Vec<[u128; 32]>,
+}
+
+impl ParityCheck {
+    pub(crate) fn new(generator: &Poly<u5>, length: usize) -> ParityCheck {
+        let checksum_len = generator.degree().unwrap();
+        let mut column = Poly::one();
+        let scaled = (0..length)
+            .map(|_| {
+                let packed = (0..checksum_len).fold(0, |acc, i| {
+                    acc | u128::from(u8::from(column.coefficient(i))) << (5 * i)
+                });
+                column = (&column * &Poly::x()).div_rem(generator).1;
+                let mut row = [0; 32];
+                for (v, entry) in row.iter_mut().enumerate() {
+                    *entry = packed_scale(packed, u5::from(v as u8), checksum_len);
+                }
+                row
+            })
+            .collect();
+        ParityCheck {
+            checksum_len,
+            scaled,
+        }
+    }
+
+    /// Checks that a checksum and length are supported, returning the
+    /// parity-check matrix of the checksum at that length
+    pub(crate) fn for_checksum(checksum: &Checksum, length: usize) -> Result<ParityCheck, String> {
+        if checksum.checksum_len() > MAX_CHECKSUM_LEN {
+            return Err(format!(
+                "checksums longer than {MAX_CHECKSUM_LEN} characters are not supported"
+            ));
+        }
+        if length <= checksum.checksum_len() {
+            return Err(format!(
+                "length {length} must exceed the checksum length {}",
+                checksum.checksum_len()
+            ));
+        }
+        let generator = checksum.generator();
+        if generator.coefficient(0) == u5::ZERO {
+            return Err("generator is divisible by x".into());
+        }
+        Ok(ParityCheck::new(&generator, length))
+    }
+
+    pub(crate) fn length(&self) -> usize {
+        self.scaled.len()
+    }
+
+    /// Converts a sparse codeword, as (position, value) pairs, to a string
+    fn to_string(&self, codeword: &[(usize, u5)]) -> u5String {
+        let mut ret = vec![u5::ZERO; self.length()];
+        for &(j, v) in codeword {
+            ret[self.length() - 1 - j] = v;
+        }
+        u5String::from(ret)
+    }
+
+    /// Runs one iteration of the information-set search, returning the lowest
+    /// weight codeword found
+    fn isd_iteration(&self, rng: &mut Rng) -> Option<Vec<(usize, u5)>> {
+        let (n, len) = (self.checksum_len, self.length());
+        // Shuffle the columns
+        let mut order: Vec<usize> = (0..len).collect();
+        for i in (1..len).rev() {
+            order.swap(i, rng.below(i + 1));
+        }
+        // Row-reduce, keeping the matrix as rows of coefficients
+        let mut rows: Vec<Vec<u5>> = (0..n)
+            .map(|r| {
+                order
+                    .iter()
+                    .map(|&j| packed_coeff(self.scaled[j][1], r))
+                    .collect()
+            })
+            .collect();
+        let mut pivots = Vec::with_capacity(n);
+        let mut is_pivot = vec![false; len];
+        for col in 0..len {
+            let r = pivots.len();
+            if r == n {
+                break;
+            }
+            let Some(src) = (r..n).find(|&i| rows[i][col] != u5::ZERO) else {
+                continue;
+            };
+            rows.swap(r, src);
+            let inv = rows[r][col].inverse().unwrap();
+            for entry in rows[r].iter_mut() {
+                *entry *= inv;
+            }
+            let pivot_row = rows[r].clone();
+            for (i, row) in rows.iter_mut().enumerate() {
+                let factor = row[col];
+                if i != r && factor != u5::ZERO {
+                    for (entry, &p) in row.iter_mut().zip(&pivot_row) {
+                        *entry += p * factor;
+                    }
+                }
+            }
+            pivots.push(col);
+            is_pivot[col] = true;
+        }
+        if pivots.len() < n {
+            return None;
+        }
+
+        // For each info column, the values forced at the pivot positions
+        let info: Vec<usize> = (0..len).filter(|&col| !is_pivot[col]).collect();
+        let reduced: Vec<[u128; 32]> = info
+            .iter()
+            .map(|&col| {
+                let packed = (0..n).fold(0, |acc, r| {
+                    acc | u128::from(u8::from(rows[r][col])) << (5 * r)
+                });
+                let mut row = [0; 32];
+                for (v, entry) in row.iter_mut().enumerate() {
+                    *entry = packed_scale(packed, u5::from(v as u8), n);
+                }
+                row
+            })
+            .collect();
+        let mut best: Option<(usize, usize, usize, u5)> = None;
+        let mut best_weight = usize::MAX;
+        for a in 0..info.len() {
+            let weight = 1 + packed_weight(reduced[a][1]);
+            if weight < best_weight {
+                best_weight = weight;
+                best = Some((a, a, 0, u5::ZERO));
+            }
+            for b in a + 1..info.len() {
+                for v in 1..32 {
+                    let weight = 2 + packed_weight(reduced[a][1] ^ reduced[b][v]);
+                    if weight < best_weight {
+                        best_weight = weight;
+                        best = Some((a, b, v, u5::from(v as u8)));
+                    }
+                }
+            }
+        }
+        let (a, b, v, value) = best?;
+        let redundancy = reduced[a][1] ^ reduced[b][v];
+        let mut codeword = vec![(order[info[a]], u5::ONE)];
+        if a != b {
+            codeword.push((order[info[b]], value));
+        }
+        for (r, &col) in pivots.iter().enumerate() {
+            let coeff = packed_coeff(redundancy, r);
+            if coeff != u5::ZERO {
+                codeword.push((order[col], coeff));
+            }
+        }
+        Some(codeword)
+    }
+
+    /// Chooses how to split codewords of weight `weight` for a meet-in-the-middle
+    /// search, into a first part of size a, whose first coefficient is 1, and a
+    /// last part of size b, making the table of last parts as big as we can
+    ///
+    /// Returns (a, b), or `None` if there would be more than `work_limit`
+    /// first parts to look up.
+    pub(crate) fn mitm_split(&self, weight: usize, work_limit: u64) -> Option<(usize, usize)> {
+        let len = self.length() as u64;
+        let binomial =
+            |n: u64, k: u64| (0..k).fold(1u64, |acc, i| acc.saturating_mul(n - i) / (i + 1));
+        let b = (1..=weight / 2)
+            .rev()
+            .find(|&b| binomial(len, b as u64).saturating_mul(31u64.pow(b as u32)) <= TABLE_LIMIT)
+            .unwrap_or(1);
+        let a = weight - b;
+        if binomial(len, a as u64).saturating_mul(31u64.saturating_pow(a as u32 - 1)) > work_limit {
+            None
+        } else {
+            Some((a, b))
+        }
+    }
+
+    /// Searches exhaustively for a codeword of weight exactly `weight`,
+    /// assuming that there are none of lower weight
+    ///
+    /// Returns `Err(())` if this would take more than `work_limit` lookups.
+    fn mitm(&self, weight: usize, work_limit: u64) -> Result<Option<Vec<(usize, u5)>>, ()> {
+        let (a, b) = self.mitm_split(weight, work_limit).ok_or(())?;
+
+        // Map each syndrome of a last part to the largest possible first
+        // position of that last part
+        let mut table = HashMap::new();
+        for first in 0..self.length() {
+            for v in 1..32 {
+                self.for_each_combination(
+                    first + 1,
+                    b - 1,
+                    self.scaled[first][v],
+                    &mut vec![],
+                    &mut |sum, _| {
+                        table.insert(sum, first);
+                        true
+                    },
+                );
+            }
+        }
+
+        // Look up the first parts, split across threads by first position
+        let n_threads = n_threads();
+        let found = AtomicBool::new(false);
+        let result = Mutex::new(None);
+        thread::scope(|scope| {
+            for t in 0..n_threads {
+                let (table, found, result) = (&table, &found, &result);
+                scope.spawn(move || {
+                    for first in (t..self.length()).step_by(n_threads) {
+                        let mut chosen = vec![(first, u5::ONE)];
+                        self.for_each_combination(
+                            first + 1,
+                            a - 1,
+                            self.scaled[first][1],
+                            &mut chosen,
+                            &mut |sum, chosen| {
+                                if found.load(Ordering::Relaxed) {
+                                    return false;
+                                }
+                                let last = chosen.last().unwrap().0;
+                                match table.get(&sum) {
+                                    Some(&start) if start > last => {
+                                        found.store(true, Ordering::Relaxed);
+                                        *result.lock().unwrap() =
+                                            Some((chosen.to_vec(), sum, start));
+                                        false
+                                    }
+                                    _ => true,
+                                }
+                            },
+                        );
+                        if found.load(Ordering::Relaxed) {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        // Reconstruct the last part of the codeword
+        let Some((mut codeword, sum, start)) = result.into_inner().unwrap() else {
+            return Ok(None);
+        };
+        for v in 1..32 {
+            let mut chosen = vec![(start, u5::from(v as u8))];
+            let mut last_part = None;
+            self.for_each_combination(
+                start + 1,
+                b - 1,
+                self.scaled[start][v],
+                &mut chosen,
+                &mut |s, chosen| {
+                    if s == sum {
+                        last_part = Some(chosen.to_vec());
+                    }
+                    last_part.is_none()
+                },
+            );
+            if let Some(last_part) = last_part {
+                codeword.extend(last_part);
+                return Ok(Some(codeword));
+            }
+        }
+        unreachable!("syndrome {sum:x} in table but not found")
+    }
+
+    /// Calls `f` on every choice of `count` further columns, with indices at
+    /// least `start`, scaled by nonzero values, with the sum of `acc` and the
+    /// scaled columns and the full list of choices; stops if `f` returns false
+    pub(crate) fn for_each_combination(
+        &self,
+        start: usize,
+        count: usize,
+        acc: u128,
+        chosen: &mut Vec<(usize, u5)>,
+        f: &mut CombinationFn,
+    ) -> bool {
+        if count == 0 {
+            return f(acc, chosen);
+        }
+        for j in start..self.length() + 1 - count {
+            for v in 1..32 {
+                chosen.push((j, u5::from(v as u8)));
+                let cont =
+                    self.for_each_combination(j + 1, count - 1, acc ^ self.scaled[j][v], chosen, f);
+                chosen.pop();
+                if !cont {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Computes the minimum distance of a checksum on strings of `length`
+/// characters (including any expanded HRP)
+///
+/// Runs `isd_iterations` iterations of the information-set search, then the
+/// exhaustive search for increasing weights, each limited to `work_limit`
+/// lookups.
+pub fn minimum_distance(
+    checksum: &Checksum,
+    length: usize,
+    isd_iterations: usize,
+    work_limit: u64,
+) -> Result<MinimumDistance, String> {
+    let matrix = ParityCheck::for_checksum(checksum, length)?;
+
+    // No single column is zero, since g does not divide x^j; within the code
+    // length the BCH bound also applies.
+    let mut lower_bound = 2;
+    if let Ok(bch) = checksum.bch_structure() {
+        if length as u64 <= bch.length {
+            lower_bound = bch.designed_distance;
+        }
+    }
+
+    // Information-set search
+    let n_threads = n_threads();
+    let best = Mutex::new(None::<Vec<(usize, u5)>>);
+    thread::scope(|scope| {
+        for t in 0..n_threads {
+            let (matrix, best) = (&matrix, &best);
+            scope.spawn(move || {
+                let mut rng = Rng::for_thread(t);
+                for _ in (t..isd_iterations).step_by(n_threads) {
+                    if let Some(codeword) = matrix.isd_iteration(&mut rng) {
+                        let mut best = best.lock().unwrap();
+                        if best.as_ref().is_none_or(|b| codeword.len() < b.len()) {
+                            *best = Some(codeword);
+                        }
+                        if best.as_ref().unwrap().len() <= lower_bound {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    let mut best = best.into_inner().unwrap();
+
+    // Exhaustive search for lighter codewords
+    while best.as_ref().is_none_or(|b| lower_bound < b.len()) {
+        match matrix.mitm(lower_bound, work_limit) {
+            Ok(Some(codeword)) => best = Some(codeword),
+            Ok(None) => lower_bound += 1,
+            Err(()) => break,
+        }
+    }
+
+    Ok(MinimumDistance {
+        length,
+        lower_bound,
+        upper_bound: best.as_ref().map(Vec::len),
+        codeword: best.map(|codeword| matrix.to_string(&codeword)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum32::get_checksums;
+
+    /// Checks that a codeword is nonzero and divisible by the generator
+    fn check_codeword(checksum: &Checksum, result: &MinimumDistance) {
+        let codeword = result.codeword.as_ref().unwrap();
+        assert_eq!(codeword.len(), result.length);
+        let weight = codeword[..].iter().filter(|&&c| c != u5::ZERO).count();
+        assert_eq!(Some(weight), result.upper_bound);
+        let poly = Poly::from_be_u5string(codeword);
+        assert!(poly.div_rem(&checksum.generator()).1.is_zero());
+    }
+
+    #[test]
+    fn packed_helpers() {
+        let x = 0b00011_00000_00001_u128;
+        assert_eq!(packed_weight(x), 2);
+        assert_eq!(packed_coeff(x, 2), u5::from(3));
+        assert_eq!(packed_scale(x, u5::from(2), 3), 0b00110_00000_00010);
+    }
+
+    #[test]
+    fn bech32() {
+        let checksums = get_checksums();
+        let bech32 = &checksums["bech32"];
+        // BIP-173 guarantees detection of 4 errors up to length 89, but not 90
+        for (length, distance) in [(20, 5), (90, 4), (1024, 2)] {
+            let result = minimum_distance(bech32, length, 100, 1 << 24).unwrap();
+            assert!(result.is_exact(), "{result}");
+            assert_eq!(result.lower_bound, distance);
+            check_codeword(bech32, &result);
+        }
+    }
+
+    #[test]
+    fn codex32() {
+        let checksums = get_checksums();
+        let codex32 = &checksums["codex32"];
+        for length in [48, 74] {
+            let result = minimum_distance(codex32, length, 1000, 0).unwrap();
+            assert_eq!(
+                result.to_string().lines().next(),
+                Some(&format!("length {length}: distance 9")[..])
+            );
+            check_codeword(codex32, &result);
+        }
+
+        // With no work allowed and no search, only the BCH bound is known
+        let result = minimum_distance(codex32, 48, 0, 0).unwrap();
+        assert_eq!(result.upper_bound, None);
+        assert_eq!(result.to_string(), "length 48: distance at least 9");
+        assert!(minimum_distance(codex32, 13, 100, 0).is_err());
+    }
+}
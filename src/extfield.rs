@@ -0,0 +1,735 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Extension Fields
+///
+/// Generic extension fields GF(32^k) of GF(32), defined by a monic irreducible
+/// polynomial of degree k chosen at runtime. Unlike `Fe1024`, which is fixed
+/// at compile time, these can be used to analyze a checksum whose generator
+/// does not split over GF(1024).
+///
+/// Elements are packed as k 5-bit coefficients in a `u64`, which limits the
+/// degree of the extension to `MAX_DEGREE`. Each element carries a copy of its
+/// field, except for the constants `ZERO` and `ONE` (and anything else created
+/// with `Field::from_u5`), which are elements of GF(32) and can be freely
+/// mixed with elements of any extension.
+///
+use crate::base32::u5;
+use crate::field::Field;
+use crate::poly::Poly;
+use std::{fmt, hash, ops};
+
+/// The maximum degree of an extension field
+pub const MAX_DEGREE: usize = 12;
+
+/// Returns the `i`th 5-bit coefficient of a packed polynomial
+fn coeff(packed: u64, i: usize) -> u5 {
+    u5::from(((packed >> (5 * i)) & 0x1f) as u8)
+}
+
+/// Packs a slice of coefficients, in little-endian order, into a `u64`
+fn pack(coeffs: &[u5]) -> u64 {
+    assert!(coeffs.len() <= MAX_DEGREE);
+    coeffs
+        .iter()
+        .enumerate()
+        .fold(0, |acc, (i, &c)| acc | (u64::from(u8::from(c)) << (5 * i)))
+}
+
+/// Computes the set of distinct prime factors of a number, by trial division
+///
+/// This is slow in general, but the only numbers we factor are the group
+/// orders 32^k - 1, whose prime factors are all small.
+fn prime_factors(mut n: u64) -> Vec<u64> {
+    let mut ret = vec![];
+    let mut p = 2;
+    while p * p <= n {
+        if n.is_multiple_of(p) {
+            ret.push(p);
+            while n.is_multiple_of(p) {
+                n /= p;
+            }
+        }
+        p += 1;
+    }
+    if n > 1 {
+        ret.push(n);
+    }
+    ret
+}
+
+/// An extension field GF(32^k), defined by a monic irreducible polynomial
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ExtField {
+    /// The coefficients of x^0 through x^{k-1} of the modulus (whose leading
+    /// coefficient is an implicit 1), packed
+    modulus: u64,
+    /// The degree k of the field over GF(32)
+    degree: usize,
+}
+
+impl ExtField {
+    /// Constructs a new extension field from a modulus
+    ///
+    /// The modulus must be monic and irreducible, with degree between 1 and
+    /// `MAX_DEGREE`.
+    pub fn new(modulus: &Poly<u5>) -> Result<ExtField, String> {
+        let degree = match modulus.degree() {
+            Some(d) => d,
+            None => return Err("modulus must not be the zero polynomial".into()),
+        };
+        if modulus.leading_coefficient() != u5::ONE {
+            return Err(format!("modulus {modulus:?} is not monic"));
+        }
+        if degree == 0 || degree > MAX_DEGREE {
+            return Err(format!(
+                "modulus has degree {degree}, which is not between 1 and {MAX_DEGREE}"
+            ));
+        }
+        let field = ExtField {
+            modulus: pack(&modulus.coefficients()[..degree]),
+            degree,
+        };
+        if field.is_irreducible() {
+            Ok(field)
+        } else {
+            Err(format!("modulus {modulus:?} is not irreducible"))
+        }
+    }
+
+    /// Constructs an extension field of the given degree
+    ///
+    /// The modulus is chosen to be the first (in lexicographic order of its
+    /// coefficients, from x^0 up) irreducible polynomial for which x is a
+    /// primitive element. So `generator()` will be primitive for fields
+    /// constructed this way.
+    pub fn with_degree(degree: usize) -> ExtField {
+        assert!(
+            (1..=MAX_DEGREE).contains(&degree),
+            "degree {degree} is not between 1 and {MAX_DEGREE}"
+        );
+        for modulus in 0..1u64 << (5 * degree) {
+            let field = ExtField { modulus, degree };
+            if field.is_irreducible() && field.is_primitive(field.generator()) {
+                return field;
+            }
+        }
+        unreachable!("primitive polynomials exist in every degree")
+    }
+
+    /// Rabin's irreducibility test
+    ///
+    /// Until this test passes the "field" is just a quotient ring, but our
+    /// arithmetic works equally well there.
+    fn is_irreducible(&self) -> bool {
+        // Compute x^(32^i) for i in 0..=k
+        let mut frob = Vec::with_capacity(self.degree + 1);
+        frob.push(self.generator());
+        for i in 0..self.degree {
+            frob.push(frob[i].frobenius());
+        }
+        // x^(32^k) must be x
+        if frob[self.degree] != self.generator() {
+            return false;
+        }
+        // And x^(32^(k/p)) - x must be coprime to the modulus for all p | k
+        prime_factors(self.degree as u64).into_iter().all(|p| {
+            let diff = frob[self.degree / p as usize] + self.generator();
+            let diff = Poly::from_coefficients(diff.coefficients());
+            diff.gcd(&self.modulus()) == Poly::one()
+        })
+    }
+
+    /// The degree of the field over GF(32)
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// The number of elements in the field, 32^k
+    pub fn size(&self) -> u64 {
+        1 << (5 * self.degree)
+    }
+
+    /// The modulus defining the field
+    pub fn modulus(&self) -> Poly<u5> {
+        let mut coeffs: Vec<u5> = (0..self.degree).map(|i| coeff(self.modulus, i)).collect();
+        coeffs.push(u5::ONE);
+        Poly::from_coefficients(coeffs)
+    }
+
+    /// Constructs an element of the field from its little-endian coefficients
+    /// with respect to the basis 1, x, x^2, ..., x^{k-1}
+    pub fn element(&self, coeffs: &[u5]) -> ExtElem {
+        assert!(
+            coeffs.len() <= self.degree,
+            "too many coefficients ({}) for a field of degree {}",
+            coeffs.len(),
+            self.degree,
+        );
+        ExtElem {
+            field: Some(*self),
+            value: pack(coeffs),
+        }
+    }
+
+    /// Embeds an element of GF(32) into the field
+    ///
+    /// Unlike `ExtElem::from_u5`, the resulting element is tagged with the field.
+    pub fn embed(&self, x: u5) -> ExtElem {
+        ExtElem {
+            field: Some(*self),
+            value: u8::from(x).into(),
+        }
+    }
+
+    /// Tags an element with this field, if it is an element of GF(32) which
+    /// is not yet tagged with any field
+    ///
+    /// Panics if the element belongs to a different field.
+    pub fn coerce(&self, x: ExtElem) -> ExtElem {
+        match x.field {
+            Some(field) => {
+                assert_eq!(
+                    field, *self,
+                    "tried to coerce element into a different field"
+                );
+                x
+            }
+            None => ExtElem {
+                field: Some(*self),
+                value: x.value,
+            },
+        }
+    }
+
+    /// The element x, which generates the field over GF(32)
+    pub fn generator(&self) -> ExtElem {
+        if self.degree == 1 {
+            // x is equal to minus the constant term of the modulus
+            self.element(&[coeff(self.modulus, 0)])
+        } else {
+            self.element(&[u5::ZERO, u5::ONE])
+        }
+    }
+
+    /// Iterates over every element of the field, starting with zero
+    ///
+    /// This is only practical for small fields.
+    pub fn elements(&self) -> impl Iterator<Item = ExtElem> {
+        let field = *self;
+        (0..self.size()).map(move |value| ExtElem {
+            field: Some(field),
+            value,
+        })
+    }
+
+    /// Whether the element generates the multiplicative group of the field
+    pub fn is_primitive(&self, elem: ExtElem) -> bool {
+        elem.multiplicative_order() == Some(self.size() - 1)
+    }
+
+    /// Finds a primitive element of the field
+    ///
+    /// This is the generator x if it is primitive; otherwise the first
+    /// primitive element found by a search through the field.
+    pub fn primitive_element(&self) -> ExtElem {
+        if self.is_primitive(self.generator()) {
+            return self.generator();
+        }
+        self.elements()
+            .find(|elem| self.is_primitive(*elem))
+            .expect("every finite field has a primitive element")
+    }
+
+    /// Finds all the distinct roots in this field of a polynomial over GF(32)
+    pub fn roots(&self, poly: &Poly<u5>) -> Vec<ExtElem> {
+        if poly.is_zero() {
+            panic!("Tried to find the roots of the zero polynomial");
+        }
+        // gcd(f, x^q - x) is the product of (x - r) for each distinct root r
+        let xq = Poly::x().pow_mod(self.size(), poly);
+        let linear = poly.gcd(&(&xq + &Poly::x()));
+
+        let mut ret = vec![];
+        self.split_linear(linear.embed(), &mut ret);
+        ret
+    }
+
+    /// Splits a product of distinct linear factors, pushing its roots onto `ret`
+    ///
+    /// This uses the trace-based splitting of Cantor and Zassenhaus, but rather
+    /// than choosing random elements δ we iterate through a GF(2)-basis of the
+    /// field. For any two distinct roots a and b, some basis element δ will have
+    /// Tr(δa) ≠ Tr(δb), so this always succeeds.
+    fn split_linear(&self, poly: Poly<ExtElem>, ret: &mut Vec<ExtElem>) {
+        match poly.degree() {
+            None | Some(0) => return,
+            Some(1) => {
+                let root = poly.coefficient(0) / poly.coefficient(1);
+                ret.push(match root.to_u5() {
+                    Some(x) => self.embed(x),
+                    None => root,
+                });
+                return;
+            }
+            Some(_) => {}
+        }
+
+        for i in 0..self.degree {
+            for j in 0..5 {
+                let mut coeffs = vec![u5::ZERO; i + 1];
+                coeffs[i] = u5::alpha_pow(j);
+                let delta = self.element(&coeffs);
+
+                // Compute the absolute trace of δx, mod our polynomial
+                let mut term = Poly::monomial(delta, 1).div_rem(&poly).1;
+                let mut trace = term.clone();
+                for _ in 1..5 * self.degree {
+                    term = (&term * &term).div_rem(&poly).1;
+                    trace += &term;
+                }
+
+                let factor = poly.gcd(&trace);
+                if factor.degree() > Some(0) && factor.degree() < poly.degree() {
+                    let cofactor = poly.div_rem(&factor).0;
+                    self.split_linear(factor, ret);
+                    self.split_linear(cofactor, ret);
+                    return;
+                }
+            }
+        }
+        unreachable!("some basis element must split a product of distinct linear factors");
+    }
+}
+
+impl fmt::Debug for ExtField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "GF(32^{})[{}]",
+            self.degree,
+            self.modulus().to_le_u5string(self.degree + 1)
+        )
+    }
+}
+
+/// An element of an extension field `ExtField`
+#[derive(Copy, Clone)]
+pub struct ExtElem {
+    /// The field that the element lives in, or `None` if it lies in GF(32)
+    field: Option<ExtField>,
+    /// The coefficients of the element, packed
+    value: u64,
+}
+
+impl ExtElem {
+    /// The field that this element lives in, or `None` if it was constructed
+    /// from an element of GF(32) without reference to any extension
+    pub fn field(&self) -> Option<ExtField> {
+        self.field
+    }
+
+    /// The little-endian coefficients of the element with respect to the
+    /// basis 1, x, x^2, ..., x^{k-1}
+    pub fn coefficients(&self) -> Vec<u5> {
+        let degree = self.field.map(|f| f.degree).unwrap_or(1);
+        (0..degree).map(|i| coeff(self.value, i)).collect()
+    }
+
+    /// Returns the element as a GF(32) element, if it lies in the subfield
+    pub fn to_u5(self) -> Option<u5> {
+        if self.value < 32 {
+            Some(coeff(self.value, 0))
+        } else {
+            None
+        }
+    }
+
+    /// The size of the multiplicative group that the element lives in
+    fn group_order(&self) -> u64 {
+        self.field.map(|f| f.size()).unwrap_or(32) - 1
+    }
+
+    /// Computes the multiplicative order of the element, or `None` if it is zero
+    pub fn multiplicative_order(self) -> Option<u64> {
+        if self.value == 0 {
+            return None;
+        }
+        let mut order = self.group_order();
+        for p in prime_factors(order) {
+            while order.is_multiple_of(p) && self.pow((order / p) as usize) == ExtElem::ONE {
+                order /= p;
+            }
+        }
+        Some(order)
+    }
+
+    /// Computes the conjugates of the element over GF(32), i.e. its images
+    /// under repeated application of the Frobenius map
+    pub fn conjugates(self) -> Vec<ExtElem> {
+        let mut ret = vec![self];
+        let mut next = self.frobenius();
+        while next != self {
+            ret.push(next);
+            next = next.frobenius();
+        }
+        ret
+    }
+
+    /// Computes the minimal polynomial of the element over GF(32)
+    pub fn minimal_polynomial(self) -> Poly<u5> {
+        // Multiply together (X - c) for each conjugate c
+        let product = self
+            .conjugates()
+            .into_iter()
+            .map(|c| Poly::from_coefficients(vec![c, ExtElem::ONE]))
+            .fold(Poly::one(), |acc, lin| &acc * &lin);
+        Poly::from_coefficients(
+            product
+                .coefficients()
+                .iter()
+                .map(|c| {
+                    c.to_u5()
+                        .expect("minimal polynomial has coefficients in GF(32)")
+                })
+                .collect(),
+        )
+    }
+
+    /// Determines the common field of two elements
+    fn common_field(&self, other: &ExtElem) -> Option<ExtField> {
+        match (self.field, other.field) {
+            (Some(f), Some(g)) => {
+                assert_eq!(f, g, "tried to combine elements of different fields");
+                Some(f)
+            }
+            (f, g) => f.or(g),
+        }
+    }
+}
+
+impl Field for ExtElem {
+    const ZERO: ExtElem = ExtElem {
+        field: None,
+        value: 0,
+    };
+    const ONE: ExtElem = ExtElem {
+        field: None,
+        value: 1,
+    };
+
+    fn from_u5(x: u5) -> ExtElem {
+        ExtElem {
+            field: None,
+            value: u8::from(x).into(),
+        }
+    }
+
+    fn inverse(self) -> Option<ExtElem> {
+        if self.value == 0 {
+            None
+        } else {
+            Some(self.pow((self.group_order() - 1) as usize))
+        }
+    }
+}
+
+impl PartialEq for ExtElem {
+    fn eq(&self, other: &ExtElem) -> bool {
+        self.common_field(other);
+        self.value == other.value
+    }
+}
+impl Eq for ExtElem {}
+
+impl hash::Hash for ExtElem {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl fmt::Display for ExtElem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.coefficients().iter().rev() {
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ExtElem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ExtElem({self})")
+    }
+}
+
+impl ops::Add for ExtElem {
+    type Output = ExtElem;
+    fn add(self, other: ExtElem) -> ExtElem {
+        ExtElem {
+            field: self.common_field(&other),
+            value: self.value ^ other.value,
+        }
+    }
+}
+impl ops::AddAssign for ExtElem {
+    fn add_assign(&mut self, other: ExtElem) {
+        *self = *self + other;
+    }
+}
+
+impl ops::Mul for ExtElem {
+    type Output = ExtElem;
+    fn mul(self, other: ExtElem) -> ExtElem {
+        let field = match self.common_field(&other) {
+            Some(field) => field,
+            None => {
+                // Both elements are in GF(32)
+                let prod = coeff(self.value, 0) * coeff(other.value, 0);
+                return ExtElem::from_u5(prod);
+            }
+        };
+
+        // Schoolbook multiplication...
+        let k = field.degree;
+        let mut prod = [u5::ZERO; 2 * MAX_DEGREE - 1];
+        for i in 0..k {
+            let a = coeff(self.value, i);
+            if a == u5::ZERO {
+                continue;
+            }
+            for j in 0..k {
+                prod[i + j] += a * coeff(other.value, j);
+            }
+        }
+        // ...followed by reduction, from the top down
+        for i in (k..2 * k - 1).rev() {
+            let top = prod[i];
+            if top == u5::ZERO {
+                continue;
+            }
+            for j in 0..k {
+                prod[i - k + j] += top * coeff(field.modulus, j);
+            }
+        }
+        ExtElem {
+            field: Some(field),
+            value: pack(&prod[..k]),
+        }
+    }
+}
+impl ops::MulAssign for ExtElem {
+    fn mul_assign(&mut self, other: ExtElem) {
+        *self = *self * other;
+    }
+}
+
+impl ops::Div for ExtElem {
+    type Output = ExtElem;
+    fn div(self, other: ExtElem) -> ExtElem {
+        match other.inverse() {
+            Some(inv) => self * inv,
+            None => panic!("Tried to divide {:?} by zero", self),
+        }
+    }
+}
+impl ops::DivAssign for ExtElem {
+    fn div_assign(&mut self, other: ExtElem) {
+        *self = *self / other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base32::u5String;
+    use crate::gf1024::Fe1024;
+    use std::str::FromStr;
+
+    fn le(s: &str) -> Poly<u5> {
+        Poly::from_le_u5string(&u5String::from_str(s).unwrap())
+    }
+
+    #[test]
+    fn prime_factors() {
+        assert_eq!(super::prime_factors(31), vec![31]);
+        assert_eq!(super::prime_factors(1023), vec![3, 11, 31]);
+        assert_eq!(super::prime_factors(32767), vec![7, 31, 151]);
+        assert_eq!(
+            super::prime_factors((1 << 60) - 1),
+            vec![3, 5, 7, 11, 13, 31, 41, 61, 151, 331, 1321],
+        );
+    }
+
+    #[test]
+    fn new() {
+        // x^2 + x + 3, which is the modulus used by Fe1024
+        assert!(ExtField::new(&le("rpp")).is_ok());
+        // x^2 + x = x(x + 1)
+        assert!(ExtField::new(&le("qpp")).is_err());
+        // not monic
+        assert!(ExtField::new(&le("rpz")).is_err());
+        // constant
+        assert!(ExtField::new(&le("p")).is_err());
+        // (x^2 + x + 3)^2 = x^4 + x^2 + 5
+        assert!(ExtField::new(&le("9qpqp")).is_err());
+    }
+
+    #[test]
+    fn matches_fe1024() {
+        let field = ExtField::new(&le("rpp")).unwrap();
+        let to_fe = |x: ExtElem| {
+            let coeffs = x.coefficients();
+            Fe1024::new(coeffs[0], coeffs[1])
+        };
+        for a in field.elements() {
+            for b in field.elements().step_by(7) {
+                assert_eq!(to_fe(a * b), to_fe(a) * to_fe(b));
+                assert_eq!(to_fe(a + b), to_fe(a) + to_fe(b));
+            }
+            assert_eq!(a.inverse().map(to_fe), to_fe(a).inverse());
+            assert_eq!(to_fe(a.frobenius()), to_fe(a).frobenius());
+            assert_eq!(
+                a.multiplicative_order(),
+                to_fe(a).multiplicative_order().map(|n| n as u64),
+            );
+        }
+    }
+
+    #[test]
+    fn field_axioms() {
+        for degree in 1..=3 {
+            let field = ExtField::with_degree(degree);
+            assert_eq!(field.degree(), degree);
+            let step = (field.size() as usize / 40).max(1);
+            let sample: Vec<ExtElem> = field.elements().step_by(step).collect();
+            for &a in &sample {
+                assert_eq!(a * ExtElem::ONE, a);
+                assert_eq!(a + ExtElem::ZERO, a);
+                if a != ExtElem::ZERO {
+                    assert_eq!(a * a.inverse().unwrap(), ExtElem::ONE);
+                }
+                for &b in &sample {
+                    assert_eq!(a * b, b * a);
+                    for &c in &sample {
+                        assert_eq!((a * b) * c, a * (b * c));
+                        assert_eq!(a * (b + c), a * b + a * c);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn primitive_elements() {
+        for degree in 1..=MAX_DEGREE {
+            let field = ExtField::with_degree(degree);
+            let gen = field.generator();
+            assert!(field.is_primitive(gen));
+            assert_eq!(field.primitive_element(), gen);
+            assert_eq!(gen.pow((field.size() - 1) as usize), ExtElem::ONE);
+            assert_eq!(gen.conjugates().len(), degree);
+        }
+
+        // Fe1024's modulus happens to be primitive, but x^2 + x + 9 is not
+        let field = ExtField::new(&le("fpp")).unwrap();
+        assert_eq!(field.generator().multiplicative_order(), Some(341));
+        let prim = field.primitive_element();
+        assert_eq!(prim.multiplicative_order(), Some(1023));
+        assert_eq!(prim.pow(341).multiplicative_order(), Some(3));
+    }
+
+    #[test]
+    fn minimal_polynomials() {
+        let field = ExtField::with_degree(3);
+        // The minimal polynomial of the generator is the modulus
+        assert_eq!(field.generator().minimal_polynomial(), field.modulus());
+        // Elements of GF(32) have linear minimal polynomials
+        let x = ExtElem::from_u5(u5::from(7));
+        assert_eq!(x.minimal_polynomial(), le("8p"));
+        // GF(32^3) contains no elements of GF(32^2) except those in GF(32)
+        for elem in field.elements().step_by(101) {
+            assert!([Some(1), Some(3)].contains(&elem.minimal_polynomial().degree()));
+        }
+    }
+
+    /// Checks that the product of the minimal polynomials of the roots of
+    /// a checksum generator is the generator itself
+    fn check_splits(modulus: &str, degree: usize) {
+        let modulus = le(modulus);
+        let field = ExtField::with_degree(degree);
+        let roots: Vec<ExtElem> = field
+            .elements()
+            .filter(|&x| modulus.evaluate_in(x) == ExtElem::ZERO)
+            .collect();
+        assert_eq!(Some(roots.len()), modulus.degree());
+
+        let mut product = Poly::one();
+        let mut seen = vec![];
+        for root in roots {
+            if seen.contains(&root) {
+                continue;
+            }
+            seen.extend(root.conjugates());
+            product *= &root.minimal_polynomial();
+        }
+        assert_eq!(product, modulus);
+    }
+
+    #[test]
+    fn roots() {
+        for modulus in [
+            "ja45kap",
+            "sscmleeeqg3mep",
+            "hyk9x4hx4ef6e20p",
+            "rpp",
+            "9qpqp",
+        ] {
+            let modulus = le(modulus);
+            let field = ExtField::with_degree(2);
+            let mut roots = field.roots(&modulus);
+            let mut brute: Vec<ExtElem> = field
+                .elements()
+                .filter(|&x| modulus.evaluate_in(x) == ExtElem::ZERO)
+                .collect();
+            roots.sort_by_key(|x| x.value);
+            brute.sort_by_key(|x| x.value);
+            assert_eq!(roots, brute);
+            assert!(roots.iter().all(|r| r.field() == Some(field)));
+        }
+
+        // A degree-3 polynomial has no roots in GF(32^2) but three in GF(32^3)
+        let cubic = ExtField::with_degree(3).modulus();
+        assert!(ExtField::with_degree(2).roots(&cubic).is_empty());
+        let field = ExtField::with_degree(3);
+        let roots = field.roots(&cubic);
+        assert_eq!(roots.len(), 3);
+        assert!(roots.contains(&field.generator()));
+
+        // Works in larger fields
+        let field = ExtField::with_degree(6);
+        let roots = field.roots(&ExtField::with_degree(3).modulus());
+        assert_eq!(roots.len(), 3);
+        for root in roots {
+            assert_eq!(root.minimal_polynomial(), cubic);
+        }
+    }
+
+    #[test]
+    fn checksum_generators_split() {
+        check_splits("ja45kap", 2);
+        check_splits("sscmleeeqg3mep", 2);
+        check_splits("hyk9x4hx4ef6e20p", 2);
+    }
+}
@@ -0,0 +1,92 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Fields
+///
+/// Generic trait for the finite fields of characteristic 2 that we work with:
+/// GF(32) itself and its various extension fields, in which the roots of our
+/// checksum generators live.
+///
+/// Since all our fields have characteristic 2, addition and subtraction are
+/// the same operation and we do not require a `Sub` or `Neg` impl.
+///
+use crate::base32::u5;
+use std::{fmt, ops};
+
+/// A finite field of characteristic 2 containing GF(32)
+pub trait Field:
+    Copy
+    + Eq
+    + fmt::Debug
+    + ops::Add<Output = Self>
+    + ops::AddAssign
+    + ops::Mul<Output = Self>
+    + ops::MulAssign
+    + ops::Div<Output = Self>
+    + ops::DivAssign
+{
+    /// The additive identity
+    const ZERO: Self;
+    /// The multiplicative identity
+    const ONE: Self;
+
+    /// Embeds an element of GF(32) into the field
+    fn from_u5(x: u5) -> Self;
+
+    /// Computes the multiplicative inverse, or `None` if the element is zero
+    fn inverse(self) -> Option<Self>;
+
+    /// Raises the element to the `n`th power, by square-and-multiply
+    ///
+    /// By convention 0^0 is 1.
+    fn pow(self, mut n: usize) -> Self {
+        let mut sq = self;
+        let mut ret = Self::ONE;
+        while n > 0 {
+            if n & 1 == 1 {
+                ret *= sq;
+            }
+            sq *= sq;
+            n >>= 1;
+        }
+        ret
+    }
+
+    /// Computes the Frobenius map x -> x^32, i.e. the generator of the Galois
+    /// group of the field over GF(32)
+    fn frobenius(self) -> Self {
+        self.pow(32)
+    }
+}
+
+impl Field for u5 {
+    const ZERO: u5 = u5::ZERO;
+    const ONE: u5 = u5::ONE;
+
+    fn from_u5(x: u5) -> u5 {
+        x
+    }
+
+    fn inverse(self) -> Option<u5> {
+        u5::inverse(self)
+    }
+
+    fn pow(self, n: usize) -> u5 {
+        u5::pow(self, n)
+    }
+
+    fn frobenius(self) -> u5 {
+        self
+    }
+}
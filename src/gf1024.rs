@@ -0,0 +1,340 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// GF(1024)
+///
+/// The roots of the bech32 and codex32 generators do not lie in GF(32), but
+/// they all lie in its quadratic extension GF(1024). This module defines that
+/// field as GF(32)[ζ] / (ζ^2 + ζ + 3), where 3 is the GF(32) element `r`
+/// (i.e. 1 + x). With this choice ζ has order 1023, so it is a primitive
+/// element of the field.
+///
+use crate::base32::u5;
+use crate::field::Field;
+use std::{fmt, ops};
+
+/// An element of GF(1024), represented as `lo + hi * ζ` for GF(32) elements
+/// `lo` and `hi`
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Fe1024 {
+    lo: u5,
+    hi: u5,
+}
+
+impl Fe1024 {
+    /// The additive identity of the field
+    pub const ZERO: Fe1024 = Fe1024 {
+        lo: u5::ZERO,
+        hi: u5::ZERO,
+    };
+    /// The multiplicative identity of the field
+    pub const ONE: Fe1024 = Fe1024 {
+        lo: u5::ONE,
+        hi: u5::ZERO,
+    };
+    /// The element ζ which generates the field over GF(32)
+    ///
+    /// It is also a primitive element, i.e. its multiplicative order is 1023.
+    pub const ZETA: Fe1024 = Fe1024 {
+        lo: u5::ZERO,
+        hi: u5::ONE,
+    };
+
+    /// Constructs the element `lo + hi * ζ`
+    pub fn new(lo: u5, hi: u5) -> Fe1024 {
+        Fe1024 { lo, hi }
+    }
+
+    /// The coefficient of 1 when the element is written as `lo + hi * ζ`
+    pub fn lo(self) -> u5 {
+        self.lo
+    }
+
+    /// The coefficient of ζ when the element is written as `lo + hi * ζ`
+    pub fn hi(self) -> u5 {
+        self.hi
+    }
+
+    /// Returns the element as a GF(32) element, if it lies in the subfield
+    pub fn to_u5(self) -> Option<u5> {
+        if self.hi == u5::ZERO {
+            Some(self.lo)
+        } else {
+            None
+        }
+    }
+
+    /// Computes the Frobenius map x -> x^32
+    ///
+    /// This sends ζ to its conjugate ζ + 1, the other root of ζ^2 + ζ + 3.
+    pub fn frobenius(self) -> Fe1024 {
+        Fe1024 {
+            lo: self.lo + self.hi,
+            hi: self.hi,
+        }
+    }
+
+    /// Computes the norm x * x^32, which always lies in GF(32)
+    pub fn norm(self) -> u5 {
+        // (a + bζ)((a + b) + bζ) = a^2 + ab + 3b^2
+        self.lo * self.lo + self.lo * self.hi + u5::from(3) * self.hi * self.hi
+    }
+
+    /// Computes the multiplicative inverse of the element, or `None` if it is zero
+    pub fn inverse(self) -> Option<Fe1024> {
+        self.norm().inverse().map(|ninv| Fe1024 {
+            lo: (self.lo + self.hi) * ninv,
+            hi: self.hi * ninv,
+        })
+    }
+
+    /// Raises the element to the `n`th power
+    ///
+    /// By convention 0^0 is 1.
+    pub fn pow(self, n: usize) -> Fe1024 {
+        if n == 0 {
+            Fe1024::ONE
+        } else {
+            // Reduce the exponent mod 1023 while keeping it positive, so
+            // that we continue to get 0^n = 0
+            Field::pow(self, (n - 1) % 1023 + 1)
+        }
+    }
+
+    /// Computes the multiplicative order of the element, or `None` if it is zero
+    ///
+    /// This will always be a divisor of 1023 = 3 * 11 * 31.
+    pub fn multiplicative_order(self) -> Option<usize> {
+        if self == Fe1024::ZERO {
+            return None;
+        }
+        let mut order = 1023;
+        for p in [3, 11, 31] {
+            while order % p == 0 && Field::pow(self, order / p) == Fe1024::ONE {
+                order /= p;
+            }
+        }
+        Some(order)
+    }
+}
+
+impl Field for Fe1024 {
+    const ZERO: Fe1024 = Fe1024::ZERO;
+    const ONE: Fe1024 = Fe1024::ONE;
+
+    fn from_u5(x: u5) -> Fe1024 {
+        x.into()
+    }
+
+    fn inverse(self) -> Option<Fe1024> {
+        Fe1024::inverse(self)
+    }
+
+    fn frobenius(self) -> Fe1024 {
+        Fe1024::frobenius(self)
+    }
+}
+
+impl From<u5> for Fe1024 {
+    fn from(lo: u5) -> Fe1024 {
+        Fe1024 { lo, hi: u5::ZERO }
+    }
+}
+
+impl fmt::Display for Fe1024 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.hi, self.lo)
+    }
+}
+
+impl fmt::Debug for Fe1024 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}ζ + {:?}", self.hi, self.lo)
+    }
+}
+
+impl ops::Add for Fe1024 {
+    type Output = Fe1024;
+    fn add(self, other: Fe1024) -> Fe1024 {
+        Fe1024 {
+            lo: self.lo + other.lo,
+            hi: self.hi + other.hi,
+        }
+    }
+}
+impl ops::AddAssign for Fe1024 {
+    fn add_assign(&mut self, other: Fe1024) {
+        *self = *self + other;
+    }
+}
+
+impl ops::Mul for Fe1024 {
+    type Output = Fe1024;
+    fn mul(self, other: Fe1024) -> Fe1024 {
+        // (a + bζ)(c + dζ) = ac + (ad + bc)ζ + bd(ζ + 3)
+        let bd = self.hi * other.hi;
+        Fe1024 {
+            lo: self.lo * other.lo + u5::from(3) * bd,
+            hi: self.lo * other.hi + self.hi * other.lo + bd,
+        }
+    }
+}
+impl ops::MulAssign for Fe1024 {
+    fn mul_assign(&mut self, other: Fe1024) {
+        *self = *self * other;
+    }
+}
+
+impl ops::Div for Fe1024 {
+    type Output = Fe1024;
+    fn div(self, other: Fe1024) -> Fe1024 {
+        match other.inverse() {
+            Some(inv) => self * inv,
+            None => panic!("Tried to divide {:?} by zero", self),
+        }
+    }
+}
+impl ops::DivAssign for Fe1024 {
+    fn div_assign(&mut self, other: Fe1024) {
+        *self = *self / other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base32::u5String;
+    use std::str::FromStr;
+
+    fn all_fe1024() -> impl Iterator<Item = Fe1024> + Clone {
+        (0..32u8).flat_map(|hi| (0..32u8).map(move |lo| Fe1024::new(lo.into(), hi.into())))
+    }
+
+    #[test]
+    fn field_axioms() {
+        for a in all_fe1024() {
+            assert_eq!(a + Fe1024::ZERO, a);
+            assert_eq!(a * Fe1024::ONE, a);
+            assert_eq!(a + a, Fe1024::ZERO);
+            for b in all_fe1024() {
+                assert_eq!(a + b, b + a);
+                assert_eq!(a * b, b * a);
+            }
+            // Checking all triples is too slow, so just use a spread of them
+            for b in all_fe1024().step_by(37) {
+                for c in all_fe1024().step_by(41) {
+                    assert_eq!((a * b) * c, a * (b * c));
+                    assert_eq!(a * (b + c), a * b + a * c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_and_division() {
+        assert_eq!(Fe1024::ZERO.inverse(), None);
+        for a in all_fe1024().filter(|&a| a != Fe1024::ZERO) {
+            let inv = a.inverse().unwrap();
+            assert_eq!(a * inv, Fe1024::ONE);
+            assert_eq!(Fe1024::ONE / a, inv);
+            assert_eq!(a.pow(1022), inv);
+        }
+    }
+
+    #[test]
+    fn frobenius() {
+        for a in all_fe1024() {
+            assert_eq!(a.frobenius(), a.pow(32));
+            assert_eq!(a.frobenius().frobenius(), a);
+            assert_eq!(a * a.frobenius(), Fe1024::from(a.norm()));
+            // The fixed field of the Frobenius map is exactly GF(32)
+            assert_eq!(a.frobenius() == a, a.to_u5().is_some());
+        }
+    }
+
+    #[test]
+    fn embedding() {
+        for x in (0..32u8).map(u5::from) {
+            assert_eq!(Fe1024::from(x).to_u5(), Some(x));
+            for y in (0..32u8).map(u5::from) {
+                assert_eq!(Fe1024::from(x) + Fe1024::from(y), Fe1024::from(x + y));
+                assert_eq!(Fe1024::from(x) * Fe1024::from(y), Fe1024::from(x * y));
+            }
+        }
+    }
+
+    #[test]
+    fn primitive_element() {
+        assert_eq!(Fe1024::ZETA.multiplicative_order(), Some(1023));
+        assert_eq!(Fe1024::ZERO.multiplicative_order(), None);
+        assert_eq!(Fe1024::ONE.multiplicative_order(), Some(1));
+        let mut seen = std::collections::HashSet::new();
+        for i in 0..1023 {
+            assert!(seen.insert(Fe1024::ZETA.pow(i)));
+        }
+        assert_eq!(Fe1024::ZETA.pow(1023), Fe1024::ONE);
+        for a in all_fe1024() {
+            if let Some(x) = a.to_u5().filter(|&x| x != u5::ZERO) {
+                assert_eq!(
+                    a.multiplicative_order(),
+                    Some(if x == u5::ONE { 1 } else { 31 })
+                );
+            }
+        }
+    }
+
+    /// Finds all roots in GF(1024) of a generator given as a modulus string,
+    /// then returns the longest run r, rβ, rβ^2, ... of roots together with
+    /// the multiplicative order of β
+    fn longest_root_run(modulus: &str) -> (usize, usize, usize) {
+        let modulus = u5String::from_str(modulus).unwrap();
+        let eval = |x: Fe1024| {
+            modulus[..]
+                .iter()
+                .rev()
+                .fold(Fe1024::ZERO, |acc, &co| acc * x + Fe1024::from(co))
+        };
+        let roots: Vec<Fe1024> = all_fe1024().filter(|&x| eval(x) == Fe1024::ZERO).collect();
+
+        let mut best = (0, 0);
+        for &r in &roots {
+            for &s in roots.iter().filter(|&&s| s != r) {
+                let beta = s / r;
+                let mut run = 2;
+                let mut next = s * beta;
+                while next != r && roots.contains(&next) {
+                    run += 1;
+                    next *= beta;
+                }
+                if run > best.0 {
+                    best = (run, beta.multiplicative_order().unwrap());
+                }
+            }
+        }
+        (roots.len(), best.0, best.1)
+    }
+
+    #[test]
+    fn bech32_roots() {
+        // Six roots, three of which are consecutive powers of an element of
+        // order 1023 (and the other three are their Frobenius conjugates)
+        assert_eq!(longest_root_run("ja45kap"), (6, 3, 1023));
+    }
+
+    #[test]
+    fn codex32_roots() {
+        assert_eq!(longest_root_run("sscmleeeqg3mep"), (13, 8, 93));
+        assert_eq!(longest_root_run("hyk9x4hx4ef6e20p"), (15, 8, 1023));
+    }
+}
@@ -0,0 +1,354 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Alternative GF(32) Representations
+///
+/// Our `u5` type is GF(2)[x] / (x^5 + x^3 + 1), with each element encoded as
+/// the 5-bit number whose bits are its coefficients. This is the field used by
+/// bech32 and codex32, but other base32 codes (and hardware implementations)
+/// may use any of the six irreducible quintics over GF(2) as the reduction
+/// polynomial. The resulting fields are all isomorphic, but the isomorphisms
+/// do not preserve the bit encoding of elements.
+///
+/// This module defines `Gf32<R>`, GF(32) with the reduction polynomial `R`
+/// (given as a 6-bit number including the x^5 term), along with an explicit
+/// `Isomorphism` between the encodings of `Gf32<R>` and `u5`. A `Checksum`
+/// defined over `Gf32<R>` can then be instantiated with this isomorphism.
+///
+use crate::base32::{self, u5, u5String, CHARSET};
+use crate::field::Field;
+use std::{fmt, ops};
+
+/// All the irreducible quintics over GF(2), as reduction polynomials
+pub const IRREDUCIBLE_QUINTICS: [u8; 6] = [0x25, 0x29, 0x2f, 0x37, 0x3b, 0x3d];
+
+/// Finds a root of the reduction polynomial `reduction` in `u5`'s field,
+/// returning 0 if there is none (which happens iff it is not irreducible)
+///
+/// We choose the numerically-smallest root; any root would do, and each of the
+/// five choices gives a different isomorphism.
+const fn find_root(reduction: u8) -> u8 {
+    let mul = base32::mul_table(base32::REDUCTION);
+    if reduction & 0xe0 != 0x20 {
+        return 0;
+    }
+    let mut theta = 2;
+    while theta < 32 {
+        // Evaluate the polynomial at theta by Horner's rule
+        let mut acc = 0;
+        let mut i = 6;
+        while i > 0 {
+            i -= 1;
+            acc = mul[acc as usize][theta as usize] ^ ((reduction >> i) & 1);
+        }
+        if acc == 0 {
+            return theta;
+        }
+        theta += 1;
+    }
+    0
+}
+
+/// Computes the table mapping the encoding of each element of `Gf32<R>` to
+/// the encoding of its image in `u5`
+const fn iso_table(reduction: u8) -> [u8; 32] {
+    let mul = base32::mul_table(base32::REDUCTION);
+    let theta = find_root(reduction);
+    let mut powers = [1u8; 5];
+    let mut i = 1;
+    while i < 5 {
+        powers[i] = mul[powers[i - 1] as usize][theta as usize];
+        i += 1;
+    }
+    let mut ret = [0; 32];
+    let mut bits = 0;
+    while bits < 32 {
+        let mut i = 0;
+        while i < 5 {
+            if bits & (1 << i) != 0 {
+                ret[bits] ^= powers[i];
+            }
+            i += 1;
+        }
+        bits += 1;
+    }
+    ret
+}
+
+/// Inverts a permutation of 0..32
+const fn invert_table(table: [u8; 32]) -> [u8; 32] {
+    let mut ret = [0; 32];
+    let mut i = 0;
+    while i < 32 {
+        ret[table[i] as usize] = i as u8;
+        i += 1;
+    }
+    ret
+}
+
+/// An element of GF(32) constructed as GF(2)[x] mod the reduction polynomial `R`
+///
+/// `R` includes the x^5 term, so e.g. `Gf32<0x29>` is the same field, with the
+/// same encoding, as `u5`.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Gf32<const R: u8>(u8);
+
+impl<const R: u8> Gf32<R> {
+    /// Compile-time check that `R` is an irreducible quintic
+    const VALID: () = assert!(
+        find_root(R) != 0,
+        "reduction polynomial is not an irreducible quintic"
+    );
+    const EXP: [u8; 62] = base32::exp_table(R);
+    const LOG: [u8; 32] = base32::log_table(R);
+    const MUL: [[u8; 32]; 32] = base32::mul_table(R);
+    const TO_U5: [u8; 32] = iso_table(R);
+    const FROM_U5: [u8; 32] = invert_table(iso_table(R));
+
+    /// Constructs an element from its 5-bit encoding
+    pub fn from_bits(bits: u8) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        assert!(
+            bits < 32,
+            "Tried to construct Gf32 from too-large number {bits}"
+        );
+        Gf32(bits)
+    }
+
+    /// The 5-bit encoding of the element
+    pub fn to_bits(self) -> u8 {
+        self.0
+    }
+
+    /// The isomorphism between this field and `u5`
+    pub fn isomorphism() -> Isomorphism {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        Isomorphism {
+            reduction: R,
+            to_u5: Self::TO_U5,
+            from_u5: Self::FROM_U5,
+        }
+    }
+
+    /// Maps the element to its image in `u5` under `Self::isomorphism()`
+    pub fn to_u5(self) -> u5 {
+        u5::from(Self::TO_U5[usize::from(self.0)])
+    }
+
+    /// Maps an element of `u5` to its preimage under `Self::isomorphism()`
+    pub fn from_u5(x: u5) -> Self {
+        Gf32::from_bits(Self::FROM_U5[usize::from(u8::from(x))])
+    }
+}
+
+impl<const R: u8> Field for Gf32<R> {
+    const ZERO: Self = Gf32(0);
+    const ONE: Self = Gf32(1);
+
+    fn from_u5(x: u5) -> Self {
+        Gf32::from_u5(x)
+    }
+
+    fn inverse(self) -> Option<Self> {
+        if self.0 == 0 {
+            None
+        } else {
+            let log = usize::from(Self::LOG[usize::from(self.0)]);
+            Some(Gf32(Self::EXP[31 - log]))
+        }
+    }
+}
+
+impl<const R: u8> fmt::Display for Gf32<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Write::write_char(f, CHARSET[usize::from(self.0)] as char)
+    }
+}
+
+impl<const R: u8> fmt::Debug for Gf32<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{:05b}/{:x}]", self, self.0, R)
+    }
+}
+
+impl<const R: u8> ops::Add for Gf32<R> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Gf32(self.0 ^ other.0)
+    }
+}
+impl<const R: u8> ops::AddAssign for Gf32<R> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const R: u8> ops::Mul for Gf32<R> {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Gf32(Self::MUL[usize::from(self.0)][usize::from(other.0)])
+    }
+}
+impl<const R: u8> ops::MulAssign for Gf32<R> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl<const R: u8> ops::Div for Gf32<R> {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        match other.inverse() {
+            Some(inv) => self * inv,
+            None => panic!("Tried to divide {:?} by zero", self),
+        }
+    }
+}
+impl<const R: u8> ops::DivAssign for Gf32<R> {
+    fn div_assign(&mut self, other: Self) {
+        *self = *self / other;
+    }
+}
+
+/// A field isomorphism from GF(2)[x] / (some reduction polynomial) to `u5`,
+/// acting on the 5-bit encodings of elements
+///
+/// Since all our strings are parsed as `u5`s, the isomorphism is expressed as
+/// a map from `u5` to `u5`: the input is a `u5` whose bits are really the
+/// encoding of an element of the other field.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Isomorphism {
+    reduction: u8,
+    to_u5: [u8; 32],
+    from_u5: [u8; 32],
+}
+
+impl Isomorphism {
+    /// The identity map on `u5`
+    pub fn identity() -> Isomorphism {
+        Gf32::<{ base32::REDUCTION }>::isomorphism()
+    }
+
+    /// Constructs the isomorphism for a reduction polynomial chosen at runtime
+    pub fn from_reduction(reduction: u8) -> Result<Isomorphism, String> {
+        if find_root(reduction) == 0 {
+            return Err(format!(
+                "reduction polynomial {reduction:#x} is not an irreducible quintic"
+            ));
+        }
+        let to_u5 = iso_table(reduction);
+        Ok(Isomorphism {
+            reduction,
+            to_u5,
+            from_u5: invert_table(to_u5),
+        })
+    }
+
+    /// The reduction polynomial of the field that this maps from
+    pub fn reduction(&self) -> u8 {
+        self.reduction
+    }
+
+    /// Whether this is the identity map
+    pub fn is_identity(&self) -> bool {
+        self.reduction == base32::REDUCTION
+    }
+
+    /// Maps an encoded element of the other field into `u5`
+    pub fn to_u5(&self, x: u5) -> u5 {
+        u5::from(self.to_u5[usize::from(u8::from(x))])
+    }
+
+    /// Maps an element of `u5` to the encoding of its preimage in the other field
+    pub fn from_u5(&self, x: u5) -> u5 {
+        u5::from(self.from_u5[usize::from(u8::from(x))])
+    }
+
+    /// Maps every character of a string into `u5`
+    pub fn string_to_u5(&self, s: &u5String) -> u5String {
+        s[..]
+            .iter()
+            .map(|&x| self.to_u5(x))
+            .collect::<Vec<u5>>()
+            .into()
+    }
+
+    /// Maps every character of a string out of `u5`
+    pub fn string_from_u5(&self, s: &u5String) -> u5String {
+        s[..]
+            .iter()
+            .map(|&x| self.from_u5(x))
+            .collect::<Vec<u5>>()
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_isomorphism<const R: u8>() {
+        let iso = Gf32::<R>::isomorphism();
+        assert_eq!(Isomorphism::from_reduction(R), Ok(iso));
+        let all = || (0..32).map(Gf32::<R>::from_bits);
+        for a in all() {
+            assert_eq!(Gf32::<R>::from_u5(a.to_u5()), a);
+            assert_eq!(iso.to_u5(u5::from(a.to_bits())), a.to_u5());
+            if a != Gf32::ZERO {
+                assert_eq!(a * a.inverse().unwrap(), Gf32::ONE);
+            }
+            for b in all() {
+                assert_eq!((a + b).to_u5(), a.to_u5() + b.to_u5());
+                assert_eq!((a * b).to_u5(), a.to_u5() * b.to_u5());
+            }
+        }
+        assert_eq!(Gf32::<R>::ONE.to_u5(), u5::ONE);
+    }
+
+    #[test]
+    fn isomorphisms() {
+        check_isomorphism::<0x25>();
+        check_isomorphism::<0x29>();
+        check_isomorphism::<0x2f>();
+        check_isomorphism::<0x37>();
+        check_isomorphism::<0x3b>();
+        check_isomorphism::<0x3d>();
+    }
+
+    #[test]
+    fn identity() {
+        let id = Isomorphism::identity();
+        assert!(id.is_identity());
+        for x in (0..32).map(u5::from) {
+            assert_eq!(id.to_u5(x), x);
+            assert_eq!(Gf32::<0x29>::from_bits(u8::from(x)) * Gf32::from_bits(7), {
+                Gf32::from_bits(u8::from(x * u5::from(7)))
+            });
+        }
+    }
+
+    #[test]
+    fn reducible() {
+        for reduction in 0x20..0x40 {
+            assert_eq!(
+                Isomorphism::from_reduction(reduction).is_ok(),
+                IRREDUCIBLE_QUINTICS.contains(&reduction),
+            );
+        }
+        assert!(Isomorphism::from_reduction(0x09).is_err());
+        assert!(Isomorphism::from_reduction(0x69).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Checksum Identification
+///
+/// Given a string of unknown format, we try every checksum in a registry. If
+/// the string is valid under some of them we report those; otherwise we look
+/// for the nearest valid string under each, by decoding as in
+/// `Checksum::locate_errors`, so that the checksum needing the fewest
+/// corrections, which is most likely the intended one, can be reported.
+///
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::checksum32::Checksum;
+use crate::listdecode;
+
+/// The nearest valid string found under a checksum
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Nearest {
+    /// The name of the checksum
+    pub name: String,
+    /// The number of substitutions needed to reach the valid string
+    pub distance: usize,
+    /// The valid string
+    pub corrected: String,
+}
+
+/// The result of trying every checksum in a registry on a string
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Identification {
+    /// The names of the checksums under which the string is valid, sorted
+    Valid(Vec<String>),
+    /// The string is valid under no checksum
+    Invalid {
+        /// The nearest valid strings found, ordered by distance and then by
+        /// name; the first is the most likely intended checksum
+        nearest: Vec<Nearest>,
+        /// The checksums under which no valid string was found, sorted by
+        /// name, with the reason
+        unmatched: Vec<(String, String)>,
+    },
+}
+
+impl fmt::Display for Identification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Identification::Valid(names) => write!(f, "valid: {}", names.join(", ")),
+            Identification::Invalid { nearest, unmatched } => {
+                f.write_str("valid under no checksum")?;
+                for near in nearest {
+                    write!(
+                        f,
+                        "\n    {} (distance {}): {}",
+                        near.name, near.distance, near.corrected
+                    )?;
+                }
+                for (name, reason) in unmatched {
+                    write!(f, "\n    {name}: {reason}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Finds the nearest valid string to `s` under a checksum, attempting at
+/// most `work_limit` decodings
+fn nearest(checksum: &Checksum, s: &str, work_limit: usize) -> Result<(usize, String), String> {
+    checksum.check_format(s)?;
+    // Decoding only cancels the syndromes at the generator's consecutive
+    // roots, so if it has others the results must be checked
+    if let Ok(correction) = checksum.correct(s) {
+        if checksum.validate_checksum(&correction.corrected) {
+            return Ok((correction.corrections.len(), correction.corrected));
+        }
+    }
+    let bch = checksum.bch_structure()?;
+    let max_distance = (bch.correctable_errors() + 1).min(bch.detectable_errors());
+    let list = listdecode::list_decode(checksum, s, max_distance, work_limit, None)?;
+    let mut candidates = list.candidates.into_iter();
+    match candidates.find(|candidate| checksum.validate_checksum(&candidate.string)) {
+        Some(candidate) => Ok((candidate.distance(), candidate.string)),
+        None => Err(format!("no valid string within distance {max_distance}")),
+    }
+}
+
+/// Tries every checksum in `checksums` on a string (with HRP), respecting
+/// their HRP policies and length limits
+///
+/// If the string is valid under none of them, the nearest valid string under
+/// each is searched for, attempting at most `work_limit` decodings per
+/// checksum.
+pub fn identify(
+    checksums: &HashMap<String, Checksum>,
+    s: &str,
+    work_limit: usize,
+) -> Identification {
+    let mut names: Vec<_> = checksums.keys().collect();
+    names.sort();
+
+    let valid: Vec<String> = names
+        .iter()
+        .filter(|name| checksums[name.as_str()].validate_checksum(s))
+        .map(|name| name.to_string())
+        .collect();
+    if !valid.is_empty() {
+        return Identification::Valid(valid);
+    }
+
+    let mut found = vec![];
+    let mut unmatched = vec![];
+    for name in names {
+        match nearest(&checksums[name], s, work_limit) {
+            Ok((distance, corrected)) => found.push(Nearest {
+                name: name.clone(),
+                distance,
+                corrected,
+            }),
+            Err(e) => unmatched.push((name.clone(), e)),
+        }
+    }
+    // The sort is stable, so ties stay in order of name
+    found.sort_by_key(|near| near.distance);
+    Identification::Invalid {
+        nearest: found,
+        unmatched,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum32::get_checksums;
+    use crate::gf32::Isomorphism;
+
+    #[test]
+    fn valid() {
+        let checksums = get_checksums();
+        let identify = |s| identify(&checksums, s, 10_000);
+        assert_eq!(
+            identify("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+            Identification::Valid(vec!["bech32".into()]),
+        );
+        assert_eq!(
+            identify("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0").to_string(),
+            "valid: bech32m",
+        );
+        assert_eq!(
+            identify("ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw"),
+            Identification::Valid(vec!["codex32".into()]),
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let checksums = get_checksums();
+        // Two errors in a codex32 string
+        let valid = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+        let invalid = valid.replace("tests", "tesqq");
+        let Identification::Invalid { nearest, unmatched } = identify(&checksums, &invalid, 10_000)
+        else {
+            panic!("{invalid} should be invalid");
+        };
+        assert_eq!(nearest[0].name, "codex32");
+        assert_eq!(nearest[0].distance, 2);
+        assert_eq!(nearest[0].corrected, valid);
+        assert!(nearest[1..].iter().all(|near| near.distance > 2));
+        assert_eq!(nearest.len() + unmatched.len(), checksums.len());
+
+        // One error in a bech32 address
+        let invalid = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5";
+        let report = identify(&checksums, invalid, 10_000).to_string();
+        assert!(report.starts_with(
+            "valid under no checksum\n    bech32 (distance 1): bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4\n"
+        ));
+
+        // Strings too long for a checksum are not corrected under it
+        let long = format!("ms1{}", "q".repeat(100));
+        let Identification::Invalid { unmatched, .. } = identify(&checksums, &long, 10_000) else {
+            panic!("{long} should be invalid");
+        };
+        assert!(
+            unmatched
+                .iter()
+                .any(|(name, reason)| name == "codex32"
+                    && reason.contains("more than the maximum 93"))
+        );
+    }
+
+    #[test]
+    fn nonconsecutive_roots() {
+        // A generator with roots 1, α and α^5, for which decoding can give
+        // invalid strings, must not stop the other checksums being tried
+        let mut checksums = get_checksums();
+        let odd = Checksum::new_in_representation("je2p", "qqp", Isomorphism::identity());
+        checksums.insert("odd".into(), odd);
+        let Identification::Invalid { nearest, unmatched } =
+            identify(&checksums, "a1qqqpqqqzqqqnt", 10_000)
+        else {
+            panic!("a1qqqpqqqzqqqnt should be invalid");
+        };
+        assert_eq!(nearest.len() + unmatched.len(), checksums.len());
+        for near in nearest {
+            assert!(checksums[&near.name].validate_checksum(&near.corrected));
+        }
+    }
+}
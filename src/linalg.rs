@@ -0,0 +1,212 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Linear Algebra
+///
+/// Dense matrices over our fields, with just enough functionality to solve
+/// the linear systems that come up when filling erasures and analyzing the
+/// parity-check matrices of our checksums.
+///
+use crate::field::Field;
+use std::ops;
+
+/// A dense matrix, stored in row-major order
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Matrix<F> {
+    n_rows: usize,
+    n_cols: usize,
+    entries: Vec<F>,
+}
+
+impl<F: Field> Matrix<F> {
+    /// Constructs the zero matrix of the given size
+    pub fn zero(n_rows: usize, n_cols: usize) -> Self {
+        Matrix {
+            n_rows,
+            n_cols,
+            entries: vec![F::ZERO; n_rows * n_cols],
+        }
+    }
+
+    /// Constructs a matrix from a list of columns, which must all have the
+    /// same length
+    pub fn from_columns(n_rows: usize, columns: &[Vec<F>]) -> Self {
+        let mut ret = Matrix::zero(n_rows, columns.len());
+        for (j, col) in columns.iter().enumerate() {
+            assert_eq!(col.len(), n_rows, "column {j} has the wrong length");
+            for (i, &x) in col.iter().enumerate() {
+                ret[(i, j)] = x;
+            }
+        }
+        ret
+    }
+
+    /// The number of rows
+    pub fn n_rows(&self) -> usize {
+        self.n_rows
+    }
+
+    /// The number of columns
+    pub fn n_cols(&self) -> usize {
+        self.n_cols
+    }
+
+    /// Swaps two rows
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for j in 0..self.n_cols {
+            self.entries.swap(a * self.n_cols + j, b * self.n_cols + j);
+        }
+    }
+
+    /// Puts the matrix into reduced row echelon form, applying the same row
+    /// operations to `rhs` (which may be empty), and returns the pivot columns
+    fn row_reduce(&mut self, rhs: &mut [F]) -> Vec<usize> {
+        let mut pivots = vec![];
+        let mut row = 0;
+        for col in 0..self.n_cols {
+            let pivot = match (row..self.n_rows).find(|&i| self[(i, col)] != F::ZERO) {
+                Some(pivot) => pivot,
+                None => continue,
+            };
+            self.swap_rows(row, pivot);
+            if !rhs.is_empty() {
+                rhs.swap(row, pivot);
+            }
+
+            let inv = self[(row, col)].inverse().unwrap();
+            for j in 0..self.n_cols {
+                self[(row, j)] *= inv;
+            }
+            if !rhs.is_empty() {
+                rhs[row] *= inv;
+            }
+
+            for i in (0..self.n_rows).filter(|&i| i != row) {
+                let factor = self[(i, col)];
+                if factor == F::ZERO {
+                    continue;
+                }
+                for j in 0..self.n_cols {
+                    let x = self[(row, j)];
+                    self[(i, j)] += factor * x;
+                }
+                if !rhs.is_empty() {
+                    let x = rhs[row];
+                    rhs[i] += factor * x;
+                }
+            }
+
+            pivots.push(col);
+            row += 1;
+            if row == self.n_rows {
+                break;
+            }
+        }
+        pivots
+    }
+
+    /// Computes the rank of the matrix
+    pub fn rank(&self) -> usize {
+        self.clone().row_reduce(&mut []).len()
+    }
+
+    /// Solves the system `self * x = rhs`, returning the unique solution
+    ///
+    /// Returns an error if there is no solution, or if there is more than one.
+    pub fn solve(&self, rhs: &[F]) -> Result<Vec<F>, String> {
+        assert_eq!(
+            rhs.len(),
+            self.n_rows,
+            "right-hand side has the wrong length"
+        );
+        let mut reduced = self.clone();
+        let mut rhs = rhs.to_vec();
+        let pivots = reduced.row_reduce(&mut rhs);
+        if rhs[pivots.len()..].iter().any(|&x| x != F::ZERO) {
+            return Err("system has no solution".into());
+        }
+        if pivots.len() < self.n_cols {
+            return Err(format!(
+                "system has {} degrees of freedom",
+                self.n_cols - pivots.len()
+            ));
+        }
+        Ok(rhs[..self.n_cols].to_vec())
+    }
+}
+
+impl<F> ops::Index<(usize, usize)> for Matrix<F> {
+    type Output = F;
+    fn index(&self, (i, j): (usize, usize)) -> &F {
+        assert!(i < self.n_rows && j < self.n_cols);
+        &self.entries[i * self.n_cols + j]
+    }
+}
+
+impl<F> ops::IndexMut<(usize, usize)> for Matrix<F> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut F {
+        assert!(i < self.n_rows && j < self.n_cols);
+        &mut self.entries[i * self.n_cols + j]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base32::u5;
+
+    fn u5s(xs: &[u8]) -> Vec<u5> {
+        xs.iter().map(|&x| u5::from(x)).collect()
+    }
+
+    #[test]
+    fn solve() {
+        // A Vandermonde matrix is invertible
+        let points = u5s(&[1, 2, 3, 4]);
+        let columns: Vec<Vec<u5>> = (0..4)
+            .map(|j| points.iter().map(|p| p.pow(j)).collect())
+            .collect();
+        let m = Matrix::from_columns(4, &columns);
+        assert_eq!(m.rank(), 4);
+        let x = u5s(&[7, 0, 31, 9]);
+        let rhs: Vec<u5> = (0..4)
+            .map(|i| (0..4).fold(u5::ZERO, |acc, j| acc + m[(i, j)] * x[j]))
+            .collect();
+        assert_eq!(m.solve(&rhs), Ok(x));
+    }
+
+    #[test]
+    fn overdetermined() {
+        // Three equations in two unknowns, consistent
+        let m = Matrix::from_columns(3, &[u5s(&[1, 2, 3]), u5s(&[0, 1, 5])]);
+        let x = u5s(&[6, 10]);
+        let rhs: Vec<u5> = (0..3)
+            .map(|i| m[(i, 0)] * x[0] + m[(i, 1)] * x[1])
+            .collect();
+        assert_eq!(m.solve(&rhs), Ok(x));
+        // ...and inconsistent
+        let mut bad = rhs.clone();
+        bad[2] += u5::ONE;
+        assert!(m.solve(&bad).is_err());
+    }
+
+    #[test]
+    fn underdetermined() {
+        // The second column is x times the first, since x * x = x^2
+        let m = Matrix::from_columns(2, &[u5s(&[1, 2]), u5s(&[2, 4])]);
+        assert_eq!(m.rank(), 1);
+        assert!(m.solve(&u5s(&[1, 2])).is_err());
+        assert_eq!(Matrix::<u5>::zero(3, 3).rank(), 0);
+    }
+}
@@ -0,0 +1,300 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// List Decoding
+///
+/// When a string has more errors than the BCH decoder can uniquely correct,
+/// there may be several valid strings nearby, and a human may be able to pick
+/// out the right one given a short list. To find every valid string within
+/// Hamming distance t of the input, with δ the designed distance, we erase
+/// every set of ρ = 2t - (δ - 1) data characters in turn and run the
+/// errors-and-erasures decoder: any valid string at distance d ≤ t differs
+/// from the input at d positions, and if a set of ρ of these is erased then
+/// the remaining 2(d - ρ) + ρ ≤ δ - 1 errors and erasures can be decoded.
+/// (If d < ρ we instead erase all of them, along with ρ - d correct ones.)
+///
+/// This costs one decoding per set, i.e. (n choose ρ) for n data characters,
+/// so the search takes a work limit and reports whether it was completed.
+///
+use std::collections::HashSet;
+
+use crate::base32::u5String;
+use crate::checksum32::Checksum;
+use crate::decode;
+
+/// A model of which characters are likely to be confused with each other, used
+/// to rank candidates at the same distance
+pub trait ConfusionModel {
+    /// The cost of having read the character `read` when `actual` was
+    /// written; lower costs are more plausible. Both are lowercase, and they
+    /// are always distinct.
+    fn cost(&self, read: char, actual: char) -> f64;
+}
+
+/// Visually similar pairs of characters from the bech32 alphabet
+pub const VISUAL_CONFUSIONS: [(char, char); 20] = [
+    ('q', 'g'),
+    ('q', '9'),
+    ('g', '9'),
+    ('g', '6'),
+    ('z', '2'),
+    ('s', '5'),
+    ('u', 'v'),
+    ('v', 'w'),
+    ('v', 'y'),
+    ('y', 'j'),
+    ('l', '7'),
+    ('t', 'f'),
+    ('x', 'k'),
+    ('n', 'h'),
+    ('m', 'n'),
+    ('c', 'e'),
+    ('a', 'd'),
+    ('0', 'd'),
+    ('8', '3'),
+    ('p', 'q'),
+];
+
+/// A confusion model in which any pair in `VISUAL_CONFUSIONS` (in either
+/// order) costs half as much as any other substitution
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct VisualConfusion;
+
+impl ConfusionModel for VisualConfusion {
+    fn cost(&self, read: char, actual: char) -> f64 {
+        if VISUAL_CONFUSIONS.contains(&(read, actual))
+            || VISUAL_CONFUSIONS.contains(&(actual, read))
+        {
+            0.5
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A valid string found by list decoding
+#[derive(Clone, PartialEq, Debug)]
+pub struct Candidate {
+    /// The valid string
+    pub string: String,
+    /// The substitutions needed to obtain it from the input, as (character
+    /// index, old character, new character), in order of index
+    pub changes: Vec<(usize, char, char)>,
+    /// The total cost of the substitutions under the confusion model, or the
+    /// number of substitutions if no model was given
+    pub cost: f64,
+}
+
+impl Candidate {
+    /// The Hamming distance from the input
+    pub fn distance(&self) -> usize {
+        self.changes.len()
+    }
+}
+
+/// The result of a list decoding
+#[derive(Clone, PartialEq, Debug)]
+pub struct ListDecoding {
+    /// The valid strings found, ordered by distance and then by cost
+    pub candidates: Vec<Candidate>,
+    /// The number of decodings which were attempted
+    pub work: usize,
+    /// Whether the search completed within the work limit; if not, some valid
+    /// strings within the requested distance may be missing
+    pub complete: bool,
+}
+
+/// Calls `f` on every `k`-element subset of `0..n`, in lexicographic order,
+/// stopping early if it returns false
+fn for_each_subset<F: FnMut(&[usize]) -> bool>(n: usize, k: usize, mut f: F) {
+    if k > n {
+        return;
+    }
+    let mut subset: Vec<usize> = (0..k).collect();
+    loop {
+        if !f(&subset) {
+            return;
+        }
+        // Find the rightmost element which can be incremented
+        let mut i = k;
+        while i > 0 && subset[i - 1] == n - k + i - 1 {
+            i -= 1;
+        }
+        if i == 0 {
+            return;
+        }
+        subset[i - 1] += 1;
+        for j in i..k {
+            subset[j] = subset[j - 1] + 1;
+        }
+    }
+}
+
+/// Finds every valid string within Hamming distance `max_distance` of `s`,
+/// differing only outside the HRP, attempting at most `work_limit` decodings
+///
+/// `max_distance` may be at most `BchStructure::detectable_errors`. The input
+/// must not contain erasure markers, and must be allowed by the checksum's HRP
+/// policy and length limit.
+pub fn list_decode(
+    checksum: &Checksum,
+    s: &str,
+    max_distance: usize,
+    work_limit: usize,
+    model: Option<&dyn ConfusionModel>,
+) -> Result<ListDecoding, String> {
+    checksum.check_format(s)?;
+    let bch = checksum.bch_structure()?;
+    if max_distance > bch.detectable_errors() {
+        return Err(format!(
+            "distance {max_distance} exceeds the maximum of {}",
+            bch.detectable_errors()
+        ));
+    }
+    let len = u5String::from_hrpstring(s)?.len();
+    let syndromes = checksum.syndromes(s)?;
+    let data_len = match s.rsplit_once('1') {
+        Some((_, data)) => data.chars().count(),
+        None => s.chars().count(),
+    };
+    let n_erasures = (2 * max_distance).saturating_sub(bch.detectable_errors());
+
+    let mut seen = HashSet::new();
+    let mut candidates = vec![];
+    let mut work = 0;
+    let mut complete = true;
+    for_each_subset(data_len, n_erasures, |erasures| {
+        if work == work_limit {
+            complete = false;
+            return false;
+        }
+        work += 1;
+        let errors = match decode::decode_with_erasures(bch, &syndromes, len, erasures) {
+            Ok(errors) => errors,
+            Err(_) => return true,
+        };
+        let correction = match checksum.apply_corrections(s, &errors, &[]) {
+            Ok(correction) => correction,
+            Err(_) => return true,
+        };
+        let changes: Vec<_> = correction
+            .corrections
+            .into_iter()
+            .filter(|&(_, old, new)| old != new)
+            .collect();
+        if changes.len() <= max_distance && seen.insert(correction.corrected.clone()) {
+            let cost = changes
+                .iter()
+                .map(|&(_, old, new)| match model {
+                    Some(model) => model.cost(old.to_ascii_lowercase(), new.to_ascii_lowercase()),
+                    None => 1.0,
+                })
+                .sum();
+            candidates.push(Candidate {
+                string: correction.corrected,
+                changes,
+                cost,
+            });
+        }
+        true
+    });
+
+    candidates.sort_by(|a, b| {
+        a.distance()
+            .cmp(&b.distance())
+            .then(a.cost.total_cmp(&b.cost))
+            .then_with(|| a.string.cmp(&b.string))
+    });
+    Ok(ListDecoding {
+        candidates,
+        work,
+        complete,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum32::get_checksums;
+
+    /// Replaces characters of a string at the given indices
+    fn replace(s: &str, replacements: &[(usize, char)]) -> String {
+        let mut chars: Vec<char> = s.chars().collect();
+        for &(idx, ch) in replacements {
+            chars[idx] = ch;
+        }
+        chars.into_iter().collect()
+    }
+
+    #[test]
+    fn subsets() {
+        let mut all = vec![];
+        for_each_subset(4, 2, |subset| {
+            all.push(subset.to_vec());
+            true
+        });
+        assert_eq!(
+            all,
+            [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]].map(|s| s.to_vec())
+        );
+
+        let mut count = 0;
+        for_each_subset(5, 0, |subset| {
+            assert!(subset.is_empty());
+            count += 1;
+            true
+        });
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn list_decoding() {
+        let checksums = get_checksums();
+        let codex32 = &checksums["codex32"];
+        let valid = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+
+        // Within the unique decoding radius there is exactly one candidate
+        let damaged = replace(valid, &[(7, '5')]);
+        let damaged = &damaged[..];
+        let list = list_decode(codex32, damaged, 4, 1, None).unwrap();
+        assert!(list.complete);
+        assert_eq!(list.candidates.len(), 1);
+        assert_eq!(list.candidates[0].string, valid);
+        assert_eq!(list.candidates[0].changes, [(7, '5', 't')]);
+
+        // 5 errors are beyond it, but the list contains the original
+        let damaged = replace(
+            valid,
+            &[(7, '5'), (13, 'q'), (22, 'g'), (30, 'k'), (47, '7')],
+        );
+        let damaged = &damaged[..];
+        assert!(codex32.correct(damaged).map(|c| c.corrected) != Ok(valid.into()));
+        let list = list_decode(codex32, damaged, 5, usize::MAX, Some(&VisualConfusion)).unwrap();
+        assert!(list.complete);
+        assert_eq!(list.work, 45 * 44 / 2);
+        let found = list.candidates.iter().find(|c| c.string == valid).unwrap();
+        assert_eq!(found.distance(), 5);
+        assert!(list.candidates.iter().all(|c| c.distance() <= 5));
+        for pair in list.candidates.windows(2) {
+            assert!((pair[0].distance(), pair[0].cost) <= (pair[1].distance(), pair[1].cost));
+        }
+
+        // A work limit stops the search early
+        let list = list_decode(codex32, damaged, 5, 10, None).unwrap();
+        assert!(!list.complete);
+        assert_eq!(list.work, 10);
+        // and the distance is limited
+        assert!(list_decode(codex32, damaged, 9, usize::MAX, None).is_err());
+    }
+}
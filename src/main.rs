@@ -16,23 +16,152 @@
 #![allow(clippy::suspicious_arithmetic_impl)] // this is the shittiest lint ever
 
 pub mod base32;
+pub mod bch;
+pub mod bench;
+pub mod bursts;
 pub mod checksum32;
+pub mod ct;
+pub mod decode;
+pub mod definitions;
+pub mod distance;
+pub mod extfield;
+pub mod field;
+pub mod gf1024;
+pub mod gf32;
+pub mod identify;
+pub mod linalg;
+pub mod listdecode;
+pub mod packed;
+pub mod poly;
+pub mod search;
+pub mod weights;
 
+use std::collections::HashMap;
 use std::env;
+use std::path::Path;
+
+/// The number of information-set iterations run by the "distance" action
+const DISTANCE_ISD_ITERATIONS: usize = 10_000;
+/// The maximum number of lookups per weight made by the "distance" action
+const DISTANCE_WORK_LIMIT: u64 = 1 << 32;
+
+/// The number of Monte Carlo trials per error model run by the "weights" action
+const WEIGHTS_TRIALS: u64 = 1_000_000;
+/// The maximum number of lookups per weight made by the "weights" action
+const WEIGHTS_WORK_LIMIT: u64 = 1 << 28;
+
+/// The maximum number of decodings attempted per checksum by the "identify" action
+const IDENTIFY_WORK_LIMIT: usize = 100_000;
+
+/// The maximum number of decodings attempted by the "list" action
+const LIST_WORK_LIMIT: usize = 1_000_000;
 
 fn main() {
-    let args: Vec<_> = env::args().collect();
-    if args.len() < 4 {
-        println!("Usage: {} <sum|validate> <checksum> <string>", args[0]);
+    let mut args: Vec<_> = env::args().collect();
+    let checksums = if args.get(1).map(|s| &s[..]) == Some("--defs") && args.len() > 2 {
+        let path = args.remove(2);
+        args.remove(1);
+        match definitions::load_checksums(Path::new(&path)) {
+            Ok(checksums) => checksums,
+            Err(e) => {
+                println!("ERROR: {e}");
+                return;
+            }
+        }
+    } else {
+        checksum32::get_checksums()
+    };
+    if args.len() < 3 {
+        println!(
+            "Usage: {0} [--defs <file>] <sum|validate|correct|fill|list|locate|residue|distance|weights|bursts|bench|analyze|search> <checksum|all|auto|len,n,d> [<string>]\n       {0} [--defs <file>] identify <string>",
+            args[0]
+        );
         return;
     }
 
-    let s = real_main(&args[1], &args[2], &args[3]);
+    let s = run(
+        &checksums,
+        &args[1],
+        &args[2],
+        args.get(3).map(|s| &s[..]).unwrap_or(""),
+    );
     println!("{s}");
 }
 
+/// Formats the result of a correction, listing each changed position in order
+fn format_correction(result: Result<checksum32::Correction, String>) -> String {
+    match result {
+        Ok(correction) => {
+            let mut changes: Vec<_> = correction
+                .filled
+                .into_iter()
+                .map(|change| (change, " (filled)"))
+                .chain(correction.corrections.into_iter().map(|change| (change, "")))
+                .collect();
+            changes.sort_by_key(|&((idx, _, _), _)| idx);
+            let mut ret = correction.corrected;
+            for ((idx, old, new), note) in changes {
+                ret.push_str(&format!("\n    position {idx}: {old} -> {new}{note}"));
+            }
+            ret
+        }
+        Err(e) => format!("ERROR: {e}"),
+    }
+}
+
+#[cfg(test)]
 fn real_main(action_s: &str, checksum_s: &str, s: &str) -> String {
-    let checksums = checksum32::get_checksums();
+    run(&checksum32::get_checksums(), action_s, checksum_s, s)
+}
+
+fn run(
+    checksums: &HashMap<String, checksum32::Checksum>,
+    action_s: &str,
+    checksum_s: &str,
+    s: &str,
+) -> String {
+    if action_s == "identify" {
+        // The string takes the place of the checksum name
+        return identify::identify(checksums, checksum_s, IDENTIFY_WORK_LIMIT).to_string();
+    }
+    if action_s == "search" {
+        let params: Result<Vec<u64>, _> = checksum_s.split(',').map(str::parse).collect();
+        return match params.as_deref() {
+            Ok(&[checksum_len, code_len, distance]) => {
+                match search::search(checksum_len as usize, code_len, distance as usize) {
+                    Ok(candidates) => search::report(&candidates),
+                    Err(e) => format!("ERROR: {e}"),
+                }
+            }
+            _ => "ERROR: search parameters should be <checksum length>,<code length>,<distance>"
+                .into(),
+        };
+    }
+    if matches!(action_s, "analyze" | "bursts") && checksum_s == "all" {
+        let mut names: Vec<_> = checksums.keys().collect();
+        names.sort();
+        let reports: Vec<_> = names
+            .into_iter()
+            .map(|name| format!("{name}\n{}", run(checksums, action_s, name, s)))
+            .collect();
+        return reports.join("\n\n");
+    }
+    // "auto" picks codex32 or long codex32 by the length of the string
+    let checksum_s = if checksum_s == "auto" {
+        let checksummed = match action_s {
+            "sum" => false,
+            "validate" | "correct" | "fill" | "list" | "locate" | "residue" => true,
+            _ => return format!("ERROR: action {action_s} cannot be used with auto"),
+        };
+        // (dropping the distance from a "list" argument)
+        let string = s.split(',').next().unwrap();
+        match checksum32::select_codex32(string, checksummed) {
+            Ok(name) => name,
+            Err(e) => return format!("ERROR: {e}"),
+        }
+    } else {
+        checksum_s
+    };
     let checksum = match checksums.get(checksum_s) {
         Some(checksum) => checksum,
         None => {
@@ -52,11 +181,11 @@ fn real_main(action_s: &str, checksum_s: &str, s: &str) -> String {
             };
             let mut ret = String::with_capacity(input.len() * 4);
             // lol i'll optimize this later
-            ret.push_str("[");
+            ret.push('[');
             for b in &input[..] {
                 ret.push_str(&format!("0x{:02x}, ", u8::from(*b)));
             }
-            ret.push_str("]");
+            ret.push(']');
             ret
         },
         "to_hrp_hex" => {
@@ -71,14 +200,124 @@ fn real_main(action_s: &str, checksum_s: &str, s: &str) -> String {
             }
             ret
         },
-        "sum" => checksum.checksum(s),
-        "validate" => {
-            if checksum.validate_checksum(s) {
-                "OK".into()
-            } else {
-                "BAD".into()
+        "bench" => match s.parse() {
+            Ok(n) => bench::polymod(checksum, n),
+            Err(e) => panic!("Could not parse batch size {s}: {e}"),
+        },
+        "analyze" => match checksum.bch_structure() {
+            Ok(bch) => bch.to_string(),
+            Err(e) => format!("ERROR: {e}"),
+        },
+        "residue" => {
+            let mut ret = match checksum.residue(s) {
+                Ok(residue) => format!("residue:   {residue}"),
+                Err(e) => return format!("ERROR: {e}"),
+            };
+            match checksum.syndromes(s) {
+                Ok(syndromes) => {
+                    ret.push_str("\nsyndromes:");
+                    for syndrome in syndromes {
+                        ret.push_str(&format!(" {syndrome}"));
+                    }
+                }
+                Err(e) => ret.push_str(&format!("\nsyndromes: ERROR: {e}")),
             }
+            ret
         }
+        "correct" => format_correction(checksum.correct(s)),
+        "fill" => format_correction(checksum.fill_erasures(s)),
+        "locate" => match checksum.locate_errors(s) {
+            Ok(positions) => {
+                let marks: String = (0..s.chars().count())
+                    .map(|idx| if positions.contains(&idx) { '^' } else { ' ' })
+                    .collect();
+                format!("{s}\n{}", marks.trim_end())
+            }
+            Err(e) => format!("ERROR: {e}"),
+        },
+        "distance" => {
+            let length = match s.parse::<usize>() {
+                Ok(length) => length,
+                Err(e) => return format!("ERROR: invalid length {s}: {e}"),
+            };
+            match distance::minimum_distance(
+                checksum,
+                length,
+                DISTANCE_ISD_ITERATIONS,
+                DISTANCE_WORK_LIMIT,
+            ) {
+                Ok(distance) => distance.to_string(),
+                Err(e) => format!("ERROR: {e}"),
+            }
+        }
+        "bursts" => {
+            // <length>[,<maximum burst length>], by default twice the checksum length
+            let mut params = s.splitn(2, ',').map(str::parse::<usize>);
+            let length = match params.next() {
+                Some(Ok(length)) => length,
+                _ => return format!("ERROR: invalid length {s}"),
+            };
+            let max_burst_len = match params.next() {
+                Some(Ok(max_burst_len)) => max_burst_len,
+                Some(Err(e)) => return format!("ERROR: invalid burst length in {s}: {e}"),
+                None => (2 * checksum.checksum_len()).min(length),
+            };
+            match bursts::report(checksum, length, max_burst_len) {
+                Ok(report) => report,
+                Err(e) => format!("ERROR: {e}"),
+            }
+        }
+        "weights" => {
+            let length = match s.parse::<usize>() {
+                Ok(length) => length,
+                Err(e) => return format!("ERROR: invalid length {s}: {e}"),
+            };
+            match weights::report(checksum, length, WEIGHTS_TRIALS, WEIGHTS_WORK_LIMIT) {
+                Ok(report) => report,
+                Err(e) => format!("ERROR: {e}"),
+            }
+        }
+        "list" => {
+            // <string>[,<maximum distance>], by default one more than the
+            // number of correctable errors
+            let (s, max_distance) = match s.split_once(',') {
+                Some((s, t)) => match t.parse::<usize>() {
+                    Ok(t) => (s, t),
+                    Err(e) => return format!("ERROR: invalid distance {t}: {e}"),
+                },
+                None => match checksum.bch_structure() {
+                    Ok(bch) => (s, bch.correctable_errors() + 1),
+                    Err(e) => return format!("ERROR: {e}"),
+                },
+            };
+            let model = listdecode::VisualConfusion;
+            match listdecode::list_decode(checksum, s, max_distance, LIST_WORK_LIMIT, Some(&model))
+            {
+                Ok(list) => {
+                    let mut lines: Vec<_> = list
+                        .candidates
+                        .iter()
+                        .map(|c| format!("{} (distance {}, cost {})", c.string, c.distance(), c.cost))
+                        .collect();
+                    if lines.is_empty() {
+                        lines.push(format!("no valid string within distance {max_distance}"));
+                    }
+                    if !list.complete {
+                        lines.push(format!("(search stopped after {} decodings)", list.work));
+                    }
+                    lines.join("\n")
+                }
+                Err(e) => format!("ERROR: {e}"),
+            }
+        }
+        "sum" => match checksum.try_checksum(s) {
+            Ok(summed) => summed,
+            Err(e) => format!("ERROR: {e}"),
+        },
+        "validate" => match checksum.validate(s) {
+            Ok(()) => "OK".into(),
+            Err(e) => format!("BAD: {e}"),
+        },
         x => panic!("unknown action {x}"),
     }
 }
@@ -148,6 +387,166 @@ mod tests {
         codex32_valid("ms10leetsllhdmn9m42vcsamx24zrxgs3qrl7ahwvhw4fnzrhve25gvezzyq0pgjxpzx0ysaam");
     }
 
+    #[test]
+    fn test_analyze() {
+        let report = real_main("analyze", "codex32", "");
+        assert!(report.contains("designed distance:  9"));
+        assert!(report.contains("maximum length:     93"));
+        let all = real_main("analyze", "all", "");
+        assert!(all.starts_with("bech32\n"));
+        assert!(all.contains("\n\ncodex32\n"));
+        assert!(all.contains("\n\nlong-codex32\n"));
+    }
+
+    #[test]
+    fn test_correct() {
+        assert_eq!(
+            real_main("correct", "codex32", "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlq"),
+            "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw\n    position 47: q -> w",
+        );
+        assert!(real_main("correct", "bech32", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3q5")
+            .starts_with("ERROR: uncorrectable"));
+        // Errors and erasures together
+        assert_eq!(
+            real_main("correct", "codex32", "ms10tests?xxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlq"),
+            "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw\n    position 9: ? -> x (filled)\n    position 47: q -> w",
+        );
+    }
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(
+            real_main("fill", "codex32", "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczl?"),
+            "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw\n    position 47: ? -> w (filled)",
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        let list = real_main("list", "codex32", "ms10tes5sxxxqxxxxxxxxgxxxxxxxkxxxxx4nzvca9cmczl7");
+        assert!(list
+            .lines()
+            .any(|line| line == "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw (distance 5, cost 4.5)"));
+        // The distance can be given after the string
+        let one_error = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlx";
+        assert_eq!(
+            real_main("list", "codex32", &format!("{one_error},1")),
+            "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw (distance 1, cost 1)",
+        );
+        assert_eq!(
+            real_main("list", "auto", &format!("{one_error},1")),
+            real_main("list", "codex32", &format!("{one_error},1")),
+        );
+        assert_eq!(
+            real_main("list", "codex32", "ms10tes5sxxxqxxxxxxxxgxxxxxxxkxxxxx4nzvca9cmczl7,2"),
+            "no valid string within distance 2",
+        );
+        assert!(real_main("list", "codex32", &format!("{one_error},x")).starts_with("ERROR"));
+    }
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(
+            real_main("locate", "bech32", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3q5"),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3q5\n                                        ^^",
+        );
+        assert_eq!(
+            real_main("locate", "bech32", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4\n",
+        );
+    }
+
+    #[test]
+    fn test_definitions() {
+        let path = env::temp_dir().join(format!("russell-defs-{}.txt", std::process::id()));
+        std::fs::write(&path, "[my-bech32]\nmodulus = ja45kap\nresidue = qqqqqp\n").unwrap();
+        let checksums = definitions::load_checksums(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(checksums.len(), checksum32::get_checksums().len() + 1);
+        assert_eq!(
+            run(&checksums, "validate", "my-bech32", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+            run(&checksums, "validate", "bech32", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+        );
+        assert!(definitions::load_checksums(&path).is_err());
+    }
+
+    #[test]
+    fn test_distance() {
+        assert!(real_main("distance", "bech32", "20").starts_with("length 20: distance 5\n"));
+        assert!(real_main("distance", "bech32", "twenty").starts_with("ERROR"));
+    }
+
+    #[test]
+    fn test_identify() {
+        assert_eq!(
+            real_main("identify", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", ""),
+            "valid: bech32",
+        );
+        let report = real_main("identify", "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlq", "");
+        assert!(report.starts_with(
+            "valid under no checksum\n    codex32 (distance 1): ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw\n"
+        ));
+    }
+
+    #[test]
+    fn test_auto() {
+        let short = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx";
+        assert_eq!(real_main("sum", "auto", short), real_main("sum", "codex32", short));
+        // BIP-93 counts only the data part, so 80 characters still fit codex32
+        let medium = format!("ms1{}", "q".repeat(80));
+        let summed = real_main("sum", "auto", &medium);
+        assert_eq!(summed, real_main("sum", "codex32", &medium));
+        assert_eq!(real_main("validate", "codex32", &summed), "OK");
+        let long = format!("ms10tests{}", "x".repeat(80));
+        let summed = real_main("sum", "auto", &long);
+        assert_eq!(summed, real_main("sum", "long-codex32", &long));
+        assert_eq!(real_main("validate", "auto", &summed), "OK");
+        assert!(real_main("validate", "codex32", &summed).starts_with("BAD: data part has"));
+        assert!(real_main("validate", "auto", &format!("ms1{}", "q".repeat(94))).starts_with("ERROR"));
+        assert!(real_main("distance", "auto", "20").starts_with("ERROR"));
+    }
+
+    #[test]
+    fn test_bursts() {
+        let report = real_main("bursts", "bech32", "20");
+        assert!(report.starts_with("length 20\n"));
+        assert_eq!(report.lines().count(), 15);
+        assert!(real_main("bursts", "bech32", "20,3").lines().count() == 6);
+        let all = real_main("bursts", "all", "20,2");
+        assert!(all.starts_with("bech32\nlength 20\n"));
+        assert!(all.contains("\n\ncodex32\nlength 20\n"));
+        assert!(real_main("bursts", "bech32", "20,x").starts_with("ERROR"));
+        assert!(real_main("bursts", "bech32", "20,21").starts_with("ERROR"));
+    }
+
+    #[test]
+    fn test_weights() {
+        let report = real_main("weights", "bech32", "10");
+        assert!(report.starts_with("length 10\n    weight  codewords\n         4  0\n"));
+        assert!(report.contains("adjacent swap"));
+        assert!(real_main("weights", "bech32", "6").starts_with("ERROR"));
+    }
+
+    #[test]
+    fn test_search() {
+        let report = real_main("search", "13,93,9", "");
+        assert!(report.starts_with("930 generators found\n"));
+        assert!(report.contains("\nsscmleeeqg3mep  gen=["));
+        assert!(real_main("search", "13,93", "").starts_with("ERROR"));
+    }
+
+    #[test]
+    fn test_residue() {
+        assert_eq!(
+            real_main("residue", "bech32", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+            "residue:   qqqqqq\nsyndromes: qq qq qq",
+        );
+        let bad = real_main("residue", "bech32", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5");
+        assert!(!bad.starts_with("residue:   qqqqqq"));
+        let typo = real_main("residue", "bech32", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3b4");
+        assert!(typo.starts_with("ERROR"));
+    }
+
     #[test]
     fn test_to_hrp_hex() {
         assert_eq!(real_main("to_hrp_hex", "bech32", "SECRETSHARE32"), "043381e570bf4798");
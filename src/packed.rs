@@ -0,0 +1,206 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Packed Checksum Engine
+///
+/// `Checksum::polymod` stores its residue as a vector of `u5`s, shifting it
+/// one element at a time and multiplying every generator coefficient by the
+/// outgoing character. This is simple but slow. Like the bech32 implementation
+/// in Bitcoin Core, the engine in this module instead packs the residue into
+/// a single integer and precomputes, for each of the 32 possible outgoing
+/// characters, the multiple of the generator that it contributes. Each input
+/// character then costs a shift, a mask and two XORs.
+///
+use crate::base32::{u5, u5String};
+use crate::checksum32::Checksum;
+use crate::gf32::Isomorphism;
+
+/// The maximum checksum length supported by the packed engine
+pub const MAX_CHECKSUM_LEN: usize = 25;
+
+/// Packs a big-endian string of at most `MAX_CHECKSUM_LEN` characters into an
+/// integer, with the last character in the low bits
+fn pack(s: &u5String) -> u128 {
+    s[..]
+        .iter()
+        .fold(0, |acc, &ch| (acc << 5) | u128::from(u8::from(ch)))
+}
+
+/// A checksum engine whose residue is packed into a `u128`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PackedChecksum {
+    /// The length of the checksum, in characters
+    len: usize,
+    /// For each character c, the packed residue of c * x^len
+    table: [u128; 32],
+    /// The packed target residue
+    target: u128,
+    /// Map from the checksum's field representation to ours
+    representation: Isomorphism,
+}
+
+impl PackedChecksum {
+    /// Derives a packed checksum engine from a checksum
+    pub fn new(checksum: &Checksum) -> Result<PackedChecksum, String> {
+        let len = checksum.checksum_len();
+        if len == 0 || len > MAX_CHECKSUM_LEN {
+            return Err(format!(
+                "checksum length {len} is not between 1 and {MAX_CHECKSUM_LEN}"
+            ));
+        }
+
+        // x^len is congruent to the generator minus its leading term
+        let generator = checksum.generator();
+        let mut table = [0; 32];
+        for (c, entry) in table.iter_mut().enumerate() {
+            let c = u5::from(c as u8);
+            *entry = (0..len).rev().fold(0, |acc, i| {
+                (acc << 5) | u128::from(u8::from(c * generator.coefficient(i)))
+            });
+        }
+
+        Ok(PackedChecksum {
+            len,
+            table,
+            target: pack(checksum.target_residue()),
+            representation: checksum.representation(),
+        })
+    }
+
+    /// The length of the checksum, in characters
+    pub fn checksum_len(&self) -> usize {
+        self.len
+    }
+
+    /// Multiplies the packed residue by x and adds a character
+    #[inline]
+    pub fn step(&self, residue: u128, ch: u5) -> u128 {
+        let shift = 5 * (self.len - 1);
+        let top = (residue >> shift) as usize;
+        let low = residue & ((1 << shift) - 1);
+        ((low << 5) | u128::from(u8::from(ch))) ^ self.table[top]
+    }
+
+    /// Computes the packed residue of a string of `u5`s, which must already
+    /// have been mapped into our field representation, plus the target residue
+    ///
+    /// The string is valid iff the result is 0. This matches the result of
+    /// `Checksum::polymod` with the characters packed big-endian.
+    pub fn polymod(&self, input: &[u5]) -> u128 {
+        input.iter().fold(1, |acc, &ch| self.step(acc, ch)) ^ self.target
+    }
+
+    /// Unpacks the output of `polymod` into a string
+    pub fn unpack(&self, residue: u128) -> u5String {
+        (0..self.len)
+            .rev()
+            .map(|i| u5::from(((residue >> (5 * i)) & 0x1f) as u8))
+            .collect::<Vec<u5>>()
+            .into()
+    }
+
+    /// Check whether an already-checksummed string is valid
+    ///
+    /// Like `Checksum::validate_checksum`, returns false if the string has an
+    /// invalid character.
+    pub fn validate_checksum(&self, s: &str) -> bool {
+        match u5String::from_hrpstring(s) {
+            Ok(input) => self.polymod(&self.representation.string_to_u5(&input)[..]) == 0,
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum32::get_checksums;
+    use crate::distance::Rng;
+    use crate::gf32::Gf32;
+
+    #[test]
+    fn matches_polymod() {
+        let mut rng = Rng::new(0x1234_5678_9abc_def0);
+        for checksum in get_checksums().values() {
+            let packed = PackedChecksum::new(checksum).unwrap();
+            for len in 0..200 {
+                let input = rng.u5_string(len);
+                let residue = packed.polymod(&input[..]);
+                assert_eq!(
+                    packed.unpack(residue),
+                    checksum.polymod_with(&input, u5::mul_table)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn validates_vectors() {
+        let checksums = get_checksums();
+        let bech32 = PackedChecksum::new(&checksums["bech32"]).unwrap();
+        assert!(bech32.validate_checksum("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"));
+        assert!(!bech32.validate_checksum("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5"));
+        let codex32 = PackedChecksum::new(&checksums["codex32"]).unwrap();
+        assert!(codex32.validate_checksum("ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw"));
+        assert!(codex32.validate_checksum("MS12NAMEA320ZYXWVUTSRQPNMLKJHGFEDCAXRPP870HKKQRM"));
+        assert!(!codex32.validate_checksum("ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlx"));
+        assert!(codex32.validate_checksum(
+            "ms10leetsllhdmn9m42vcsamx24zrxgs3qrl7ahwvhw4fnzrhve25gvezzyqqtum9pgv99ycma"
+        ));
+        let long = PackedChecksum::new(&checksums["long-codex32"]).unwrap();
+        let summed = checksums["long-codex32"].checksum(&format!("ms10leet{}", "q".repeat(100)));
+        assert!(long.validate_checksum(&summed));
+
+        // Invalid characters make a string invalid, as for `Checksum`
+        for s in [
+            "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlb",
+            "ms10testsixxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw",
+            "ms10tests xxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw",
+        ] {
+            assert!(!codex32.validate_checksum(s), "{s}");
+            assert_eq!(
+                codex32.validate_checksum(s),
+                checksums["codex32"].validate_checksum(s)
+            );
+        }
+    }
+
+    #[test]
+    fn other_representations_and_lengths() {
+        let iso = Gf32::<0x3d>::isomorphism();
+        let checksum = Checksum::new_in_representation("ja45kap", "qqqqqp", iso);
+        let packed = PackedChecksum::new(&checksum).unwrap();
+        let summed = checksum.checksum("abc1qqqqqqqqq");
+        assert!(packed.validate_checksum(&summed));
+
+        // The full 25 characters fit, but 26 do not
+        let max = Checksum::new_in_representation(
+            &format!("{}p", "z".repeat(25)),
+            &"q".repeat(25),
+            Isomorphism::identity(),
+        );
+        let packed = PackedChecksum::new(&max).unwrap();
+        let input = Rng::new(1).u5_string(100);
+        assert_eq!(
+            packed.unpack(packed.polymod(&input[..])),
+            max.polymod_with(&input, u5::mul_table)
+        );
+        let too_long = Checksum::new_in_representation(
+            &format!("{}p", "z".repeat(26)),
+            &"q".repeat(26),
+            Isomorphism::identity(),
+        );
+        assert!(PackedChecksum::new(&too_long).is_err());
+    }
+}
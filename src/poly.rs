@@ -0,0 +1,492 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Polynomials
+///
+/// Polynomials over GF(32) and its extension fields. Note that there are two
+/// conventions for mapping strings to polynomials in this project:
+///
+///   * the generator ("modulus") strings are little-endian, i.e. the ith
+///     character is the coefficient of x^i, so the leading coefficient comes
+///     last (and is always `p`, i.e. 1);
+///   * data strings are big-endian, i.e. the first character is the leading
+///     coefficient and the last character is the constant term.
+///
+/// `Poly` has conversion functions for both, and stores its coefficients in
+/// little-endian order.
+///
+use crate::base32::{u5, u5String};
+use crate::field::Field;
+use std::{fmt, ops};
+
+/// A polynomial with coefficients in the field `F`
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Poly<F> {
+    /// The coefficients, in little-endian order, with no trailing zeros
+    coeffs: Vec<F>,
+}
+
+impl<F: Field> Poly<F> {
+    /// The zero polynomial
+    pub fn zero() -> Self {
+        Poly { coeffs: vec![] }
+    }
+
+    /// The constant polynomial 1
+    pub fn one() -> Self {
+        Poly::constant(F::ONE)
+    }
+
+    /// The polynomial x
+    pub fn x() -> Self {
+        Poly::monomial(F::ONE, 1)
+    }
+
+    /// The constant polynomial `c`
+    pub fn constant(c: F) -> Self {
+        Poly::monomial(c, 0)
+    }
+
+    /// The polynomial `c * x^degree`
+    pub fn monomial(c: F, degree: usize) -> Self {
+        let mut coeffs = vec![F::ZERO; degree + 1];
+        coeffs[degree] = c;
+        Poly::from_coefficients(coeffs)
+    }
+
+    /// Constructs a polynomial from its coefficients, in little-endian order
+    pub fn from_coefficients(coeffs: Vec<F>) -> Self {
+        let mut ret = Poly { coeffs };
+        ret.normalize();
+        ret
+    }
+
+    /// Strips any zero leading coefficients
+    fn normalize(&mut self) {
+        while self.coeffs.last() == Some(&F::ZERO) {
+            self.coeffs.pop();
+        }
+    }
+
+    /// The coefficients of the polynomial, in little-endian order
+    ///
+    /// The final coefficient is nonzero, unless this is the zero polynomial,
+    /// in which case the slice is empty.
+    pub fn coefficients(&self) -> &[F] {
+        &self.coeffs
+    }
+
+    /// The coefficient of x^i
+    pub fn coefficient(&self, i: usize) -> F {
+        self.coeffs.get(i).copied().unwrap_or(F::ZERO)
+    }
+
+    /// The degree of the polynomial, or `None` for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coeffs.len().checked_sub(1)
+    }
+
+    /// Whether this is the zero polynomial
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    /// The leading coefficient, or zero for the zero polynomial
+    pub fn leading_coefficient(&self) -> F {
+        self.coeffs.last().copied().unwrap_or(F::ZERO)
+    }
+
+    /// Multiplies every coefficient by a constant
+    pub fn scale(&self, c: F) -> Self {
+        Poly::from_coefficients(self.coeffs.iter().map(|&a| a * c).collect())
+    }
+
+    /// Scales the polynomial so that its leading coefficient is 1
+    ///
+    /// The zero polynomial is returned unchanged.
+    pub fn monic(&self) -> Self {
+        match self.leading_coefficient().inverse() {
+            Some(inv) => self.scale(inv),
+            None => Poly::zero(),
+        }
+    }
+
+    /// Evaluates the polynomial at a point, using Horner's rule
+    pub fn evaluate(&self, x: F) -> F {
+        self.coeffs
+            .iter()
+            .rev()
+            .fold(F::ZERO, |acc, &c| acc * x + c)
+    }
+
+    /// Computes the formal derivative of the polynomial
+    ///
+    /// Since we are in characteristic 2, this simply drops the even-degree
+    /// terms and shifts the odd-degree ones down by one.
+    pub fn derivative(&self) -> Self {
+        Poly::from_coefficients(
+            self.coeffs
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, &c)| if i % 2 == 1 { c } else { F::ZERO })
+                .collect(),
+        )
+    }
+
+    /// Divides by another polynomial, returning the quotient and remainder
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        let div_degree = match divisor.degree() {
+            Some(d) => d,
+            None => panic!("Tried to divide {:?} by the zero polynomial", self),
+        };
+        let self_degree = match self.degree() {
+            Some(d) if d >= div_degree => d,
+            _ => return (Poly::zero(), self.clone()),
+        };
+
+        let lead_inv = divisor.leading_coefficient().inverse().unwrap();
+        let mut rem = self.coeffs.clone();
+        let mut quot = vec![F::ZERO; self_degree - div_degree + 1];
+        for i in (0..quot.len()).rev() {
+            let c = rem[i + div_degree] * lead_inv;
+            quot[i] = c;
+            for (j, &d) in divisor.coeffs.iter().enumerate() {
+                rem[i + j] += c * d;
+            }
+        }
+        rem.truncate(div_degree);
+        (Poly::from_coefficients(quot), Poly::from_coefficients(rem))
+    }
+
+    /// Computes the monic greatest common divisor of two polynomials
+    ///
+    /// The gcd of two zero polynomials is zero.
+    pub fn gcd(&self, other: &Self) -> Self {
+        self.extended_gcd(other).0
+    }
+
+    /// Computes the monic gcd g of two polynomials, along with polynomials s
+    /// and t such that `s * self + t * other = g`
+    pub fn extended_gcd(&self, other: &Self) -> (Self, Self, Self) {
+        let (mut r0, mut r1) = (self.clone(), other.clone());
+        let (mut s0, mut s1) = (Poly::one(), Poly::zero());
+        let (mut t0, mut t1) = (Poly::zero(), Poly::one());
+        while !r1.is_zero() {
+            let (q, r) = r0.div_rem(&r1);
+            let s = &s0 + &(&q * &s1);
+            let t = &t0 + &(&q * &t1);
+            r0 = std::mem::replace(&mut r1, r);
+            s0 = std::mem::replace(&mut s1, s);
+            t0 = std::mem::replace(&mut t1, t);
+        }
+        match r0.leading_coefficient().inverse() {
+            Some(inv) => (r0.scale(inv), s0.scale(inv), t0.scale(inv)),
+            None => (r0, s0, t0),
+        }
+    }
+
+    /// Computes `self^n mod modulus`, by square-and-multiply
+    pub fn pow_mod(&self, mut n: u64, modulus: &Self) -> Self {
+        let mut sq = self.div_rem(modulus).1;
+        let mut ret = Poly::one().div_rem(modulus).1;
+        while n > 0 {
+            if n & 1 == 1 {
+                ret = (&ret * &sq).div_rem(modulus).1;
+            }
+            sq = (&sq * &sq).div_rem(modulus).1;
+            n >>= 1;
+        }
+        ret
+    }
+}
+
+impl Poly<u5> {
+    /// Interprets a string as a polynomial whose ith character is the
+    /// coefficient of x^i, as in our modulus strings
+    pub fn from_le_u5string(s: &u5String) -> Self {
+        Poly::from_coefficients(s[..].to_vec())
+    }
+
+    /// Interprets a string as a polynomial whose first character is the
+    /// leading coefficient, as in our data strings
+    pub fn from_be_u5string(s: &u5String) -> Self {
+        Poly::from_coefficients(s[..].iter().rev().copied().collect())
+    }
+
+    /// Converts the polynomial to a little-endian string, padded with zeros
+    /// to `len` characters
+    ///
+    /// Panics if the polynomial has degree `len` or higher.
+    pub fn to_le_u5string(&self, len: usize) -> u5String {
+        assert!(
+            self.coeffs.len() <= len,
+            "polynomial {:?} does not fit in {} characters",
+            self,
+            len,
+        );
+        (0..len)
+            .map(|i| self.coefficient(i))
+            .collect::<Vec<u5>>()
+            .into()
+    }
+
+    /// Converts the polynomial to a big-endian string, padded with zeros
+    /// to `len` characters
+    ///
+    /// Panics if the polynomial has degree `len` or higher.
+    pub fn to_be_u5string(&self, len: usize) -> u5String {
+        assert!(
+            self.coeffs.len() <= len,
+            "polynomial {:?} does not fit in {} characters",
+            self,
+            len,
+        );
+        (0..len)
+            .rev()
+            .map(|i| self.coefficient(i))
+            .collect::<Vec<u5>>()
+            .into()
+    }
+
+    /// Embeds the polynomial into a polynomial over an extension field
+    pub fn embed<E: Field>(&self) -> Poly<E> {
+        Poly::from_coefficients(self.coeffs.iter().map(|&c| E::from_u5(c)).collect())
+    }
+
+    /// Evaluates the polynomial at a point in an extension field
+    pub fn evaluate_in<E: Field>(&self, x: E) -> E {
+        self.coeffs
+            .iter()
+            .rev()
+            .fold(E::ZERO, |acc, &c| acc * x + E::from_u5(c))
+    }
+}
+
+impl<F: fmt::Debug> fmt::Debug for Poly<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.coeffs.is_empty() {
+            return f.write_str("0");
+        }
+        let mut first = true;
+        for (i, c) in self.coeffs.iter().enumerate().rev() {
+            if !first {
+                f.write_str(" + ")?;
+            }
+            first = false;
+            match i {
+                0 => write!(f, "{c:?}")?,
+                1 => write!(f, "{c:?}x")?,
+                i => write!(f, "{c:?}x^{i}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Field> ops::Add<&Poly<F>> for &Poly<F> {
+    type Output = Poly<F>;
+    fn add(self, other: &Poly<F>) -> Poly<F> {
+        let len = self.coeffs.len().max(other.coeffs.len());
+        Poly::from_coefficients(
+            (0..len)
+                .map(|i| self.coefficient(i) + other.coefficient(i))
+                .collect(),
+        )
+    }
+}
+impl<F: Field> ops::Add for Poly<F> {
+    type Output = Poly<F>;
+    fn add(self, other: Poly<F>) -> Poly<F> {
+        &self + &other
+    }
+}
+impl<F: Field> ops::AddAssign<&Poly<F>> for Poly<F> {
+    fn add_assign(&mut self, other: &Poly<F>) {
+        *self = &*self + other;
+    }
+}
+
+impl<F: Field> ops::Mul<&Poly<F>> for &Poly<F> {
+    type Output = Poly<F>;
+    fn mul(self, other: &Poly<F>) -> Poly<F> {
+        if self.is_zero() || other.is_zero() {
+            return Poly::zero();
+        }
+        let mut coeffs = vec![F::ZERO; self.coeffs.len() + other.coeffs.len() - 1];
+        for (i, &a) in self.coeffs.iter().enumerate() {
+            for (j, &b) in other.coeffs.iter().enumerate() {
+                coeffs[i + j] += a * b;
+            }
+        }
+        Poly::from_coefficients(coeffs)
+    }
+}
+impl<F: Field> ops::Mul for Poly<F> {
+    type Output = Poly<F>;
+    fn mul(self, other: Poly<F>) -> Poly<F> {
+        &self * &other
+    }
+}
+impl<F: Field> ops::MulAssign<&Poly<F>> for Poly<F> {
+    fn mul_assign(&mut self, other: &Poly<F>) {
+        *self = &*self * other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gf1024::Fe1024;
+    use std::str::FromStr;
+
+    fn le(s: &str) -> Poly<u5> {
+        Poly::from_le_u5string(&u5String::from_str(s).unwrap())
+    }
+
+    #[test]
+    fn string_conversions() {
+        let s = u5String::from_str("ja45kap").unwrap();
+        let p = Poly::from_le_u5string(&s);
+        assert_eq!(p.degree(), Some(6));
+        assert_eq!(p.coefficient(0), u5::from_char('j').unwrap());
+        assert_eq!(p.leading_coefficient(), u5::ONE);
+        assert_eq!(p.to_le_u5string(7), s);
+        assert_eq!(p.to_le_u5string(9).to_string(), "ja45kapqq");
+        assert_eq!(p.to_be_u5string(7).to_string(), "pak54aj");
+        assert_eq!(p.to_be_u5string(9).to_string(), "qqpak54aj");
+        assert_eq!(Poly::from_be_u5string(&p.to_be_u5string(9)), p);
+
+        // Leading zeros are dropped
+        assert_eq!(le("pzqq"), le("pz"));
+        assert_eq!(le("qqq"), Poly::zero());
+        assert_eq!(Poly::<u5>::zero().degree(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn string_too_short() {
+        le("ja45kap").to_be_u5string(6);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = le("ja45kap");
+        let b = le("sscmleeeqg3mep");
+        assert_eq!(&a + &a, Poly::zero());
+        assert_eq!(&a * &Poly::one(), a);
+        assert_eq!(&a * &b, &b * &a);
+        assert_eq!((&a * &b).degree(), Some(19));
+        assert_eq!(&(&a + &b) * &b, &(&a * &b) + &(&b * &b),);
+        assert_eq!(&Poly::x() * &Poly::x(), Poly::monomial(u5::ONE, 2));
+    }
+
+    #[test]
+    fn div_rem() {
+        let a = le("hyk9x4hx4ef6e20p");
+        let b = le("ja45kap");
+        let (q, r) = a.div_rem(&b);
+        assert!(r.degree() < b.degree());
+        assert_eq!(&(&q * &b) + &r, a);
+
+        // Exact division
+        let (q, r) = (&a * &b).div_rem(&b);
+        assert_eq!(q, a);
+        assert!(r.is_zero());
+
+        // Dividing by a larger polynomial does nothing
+        let (q, r) = b.div_rem(&a);
+        assert!(q.is_zero());
+        assert_eq!(r, b);
+    }
+
+    #[test]
+    #[should_panic]
+    fn div_by_zero() {
+        le("ja45kap").div_rem(&Poly::zero());
+    }
+
+    #[test]
+    fn gcd() {
+        let a = le("ja45kap");
+        let b = le("sscmleeeqg3mep");
+        let c = le("hyk9x4hx4ef6e20p");
+        // The generators are pairwise coprime
+        assert_eq!(a.gcd(&b), Poly::one());
+        assert_eq!(b.gcd(&c), Poly::one());
+
+        let ab = &a * &b;
+        let ac = &a * &c;
+        let (g, s, t) = ab.extended_gcd(&ac);
+        assert_eq!(g, a);
+        assert_eq!(&(&s * &ab) + &(&t * &ac), g);
+
+        // gcd is always monic
+        let scaled = a.scale(u5::from(7));
+        assert_eq!(scaled.gcd(&scaled), a);
+        assert_eq!(Poly::<u5>::zero().gcd(&Poly::zero()), Poly::zero());
+    }
+
+    #[test]
+    fn evaluate_and_derivative() {
+        let p = le("ja45kap");
+        let x = u5::from(9);
+        assert_eq!(
+            p.evaluate(x),
+            (0..7).fold(u5::ZERO, |acc, i| acc + p.coefficient(i) * x.pow(i))
+        );
+        assert_eq!(p.evaluate_in(Fe1024::from(x)), Fe1024::from(p.evaluate(x)));
+
+        // d/dx (a + bx + cx^2 + dx^3) = b + dx^2 in characteristic 2
+        assert_eq!(
+            le("ja45kap").derivative(),
+            le("a q 5 q a".replace(' ', "").as_str())
+        );
+        assert_eq!(Poly::<u5>::one().derivative(), Poly::zero());
+
+        // Product rule
+        let q = le("sscmleeeqg3mep");
+        assert_eq!(
+            (&p * &q).derivative(),
+            &(&p.derivative() * &q) + &(&p * &q.derivative()),
+        );
+    }
+
+    #[test]
+    fn extension_field_polys() {
+        // Over GF(1024), x^2 + x + 3 factors as (x - ζ)(x - ζ^32)
+        let modulus: Poly<Fe1024> = le("rpp").embed();
+        let zeta = Fe1024::ZETA;
+        let lin = |r: Fe1024| Poly::from_coefficients(vec![r, Fe1024::ONE]);
+        assert_eq!(modulus, &lin(zeta) * &lin(zeta.frobenius()));
+        assert_eq!(modulus.evaluate(zeta), Fe1024::ZERO);
+        let (q, r) = modulus.div_rem(&lin(zeta));
+        assert_eq!(q, lin(zeta.frobenius()));
+        assert!(r.is_zero());
+    }
+
+    #[test]
+    fn pow_mod() {
+        // In GF(32)[x]/(x^2 + x + 3), x^1023 = 1 since x is primitive
+        let modulus = le("rpp");
+        assert_eq!(Poly::x().pow_mod(1023, &modulus), Poly::one());
+        assert_eq!(
+            Poly::x().pow_mod(341, &modulus).pow_mod(3, &modulus),
+            Poly::one()
+        );
+        assert_ne!(Poly::x().pow_mod(341, &modulus), Poly::one());
+    }
+}
@@ -0,0 +1,284 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// BCH Generator Search
+///
+/// Finds generators of BCH codes over GF(32) with a given checksum length,
+/// code length and designed distance, replacing Pieter's gen_bech.py. A BCH
+/// code of length n has as roots a run of consecutive powers β^c, ...,
+/// β^{c+δ-2} of an element β of order n, which lives in the smallest field
+/// GF(32^k) for which n divides 32^k - 1. Its generator is the product of the
+/// minimal polynomials of these roots, which are shared between the powers
+/// β^i whose exponents i lie in the same cyclotomic coset {i, 32i, 32^2 i, ...}
+/// mod n. So the degree of a generator, which is the length of its checksum,
+/// is the total size of the cosets that its roots touch, and we need only
+/// compute the polynomials for the root sets which have the right size.
+///
+/// We try every β of order n, i.e. every β_0^u for a fixed β_0 and u coprime
+/// to n, and every starting exponent c. Replacing u by 32u maps each root to
+/// a conjugate in the same coset, and replacing it by -u gives the same run
+/// of roots in reverse, so we need only try one u from each set {±32^m u}.
+/// Code lengths are limited to `MAX_CODE_LEN`, since the search takes time
+/// and memory proportional to the square of the length.
+///
+use std::collections::BTreeMap;
+
+use crate::base32::{u5, u5String};
+use crate::extfield::{ExtField, MAX_DEGREE};
+use crate::field::Field;
+use crate::poly::Poly;
+
+/// The maximum code length accepted by `search`
+pub const MAX_CODE_LEN: u64 = 1 << 15;
+
+/// A generator found by `search`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Candidate {
+    /// The generator polynomial
+    pub generator: Poly<u5>,
+    /// The exponent u such that β = β_0^u, where β_0 is the (q - 1)/n th power
+    /// of the primitive element of `ExtField::with_degree(k)`
+    pub beta_exponent: u64,
+    /// The exponent c of the first root β^c
+    pub first_root: u64,
+}
+
+impl Candidate {
+    /// The generator in the little-endian string form taken by
+    /// `Checksum::new_in_representation`, ending in 'p'
+    pub fn modulus_string(&self) -> String {
+        let len = self.generator.degree().unwrap() + 1;
+        self.generator.to_le_u5string(len).to_string()
+    }
+
+    /// The generator in the `gen=` list form output by gen_bech.py, which is
+    /// little-endian and includes the final 1
+    pub fn gen_list(&self) -> String {
+        let coeffs: Vec<String> = self
+            .generator
+            .coefficients()
+            .iter()
+            .map(|&c| u8::from(c).to_string())
+            .collect();
+        format!("gen=[{}]", coeffs.join(","))
+    }
+}
+
+/// Computes the greatest common divisor of two integers
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Finds all generators of BCH codes with `checksum_len` characters of
+/// checksum, length `code_len` and designed distance at least `distance`
+///
+/// Candidates are ordered by their sets of roots, and each generator is
+/// listed once, along with the first (β, c) pair found to produce it.
+pub fn search(
+    checksum_len: usize,
+    code_len: u64,
+    distance: usize,
+) -> Result<Vec<Candidate>, String> {
+    if distance < 2 || distance - 1 > checksum_len {
+        return Err(format!(
+            "distance must be between 2 and the checksum length plus 1, not {distance}"
+        ));
+    }
+    if code_len <= checksum_len as u64 {
+        return Err(format!(
+            "code length {code_len} must exceed the checksum length {checksum_len}"
+        ));
+    }
+    if code_len > MAX_CODE_LEN {
+        return Err(format!(
+            "code length {code_len} is more than the maximum {MAX_CODE_LEN}"
+        ));
+    }
+    if code_len.is_multiple_of(2) {
+        return Err(format!("code length {code_len} must be odd"));
+    }
+    let degree =
+        match (1..=MAX_DEGREE).find(|&k| (32u64.pow(k as u32) - 1).is_multiple_of(code_len)) {
+            Some(degree) => degree,
+            None => {
+                return Err(format!(
+                    "code length {code_len} does not divide 32^k - 1 for any k up to {MAX_DEGREE}"
+                ))
+            }
+        };
+
+    // Label each exponent mod n by the smallest element of its cyclotomic coset
+    let n = code_len;
+    let mut coset_rep = vec![u64::MAX; n as usize];
+    let mut coset_size = vec![0; n as usize];
+    for i in 0..n {
+        if coset_rep[i as usize] != u64::MAX {
+            continue;
+        }
+        let mut j = i;
+        let mut size = 0;
+        while coset_rep[j as usize] == u64::MAX {
+            coset_rep[j as usize] = i;
+            size += 1;
+            j = j * 32 % n;
+        }
+        coset_size[i as usize] = size;
+    }
+
+    // Find every set of cosets with the right total size, trying one u from
+    // each set {±32^m u}, i.e. those which are the smallest of their coset
+    // and its negation
+    let mut root_sets = BTreeMap::new();
+    let mut reps = Vec::with_capacity(distance - 1);
+    let steps = (1..n).filter(|&u| {
+        gcd(u, n) == 1 && coset_rep[u as usize] == u && coset_rep[(n - u) as usize] >= u
+    });
+    for u in steps {
+        'start: for c in 0..n {
+            reps.clear();
+            let mut size = 0;
+            // The exponent u(c + j) of the jth root
+            let mut exponent = u * c % n;
+            for _ in 0..distance - 1 {
+                let rep = coset_rep[exponent as usize];
+                exponent += u;
+                if exponent >= n {
+                    exponent -= n;
+                }
+                if !reps.contains(&rep) {
+                    reps.push(rep);
+                    size += coset_size[rep as usize];
+                    if size > checksum_len {
+                        continue 'start;
+                    }
+                }
+            }
+            if size == checksum_len {
+                reps.sort_unstable();
+                root_sets.entry(reps.clone()).or_insert((u, c));
+            }
+        }
+    }
+
+    let field = ExtField::with_degree(degree);
+    let beta = field
+        .primitive_element()
+        .pow(((field.size() - 1) / n) as usize);
+    let mut min_polys = BTreeMap::new();
+    Ok(root_sets
+        .into_iter()
+        .map(|(reps, (u, c))| {
+            let mut generator = Poly::one();
+            for rep in reps {
+                let min_poly = min_polys
+                    .entry(rep)
+                    .or_insert_with(|| beta.pow(rep as usize).minimal_polynomial());
+                generator *= &*min_poly;
+            }
+            Candidate {
+                generator,
+                beta_exponent: u,
+                first_root: c,
+            }
+        })
+        .collect())
+}
+
+/// Formats the results of a search, one generator per line
+pub fn report(candidates: &[Candidate]) -> String {
+    let mut ret = format!("{} generators found", candidates.len());
+    for candidate in candidates {
+        ret.push_str(&format!(
+            "\n{}  {}",
+            candidate.modulus_string(),
+            candidate.gen_list()
+        ));
+    }
+    ret
+}
+
+/// Parses a modulus string, as output by `Candidate::modulus_string`, back
+/// into a polynomial
+pub fn parse_modulus(s: &str) -> Result<Poly<u5>, String> {
+    let string: u5String = s.parse()?;
+    Ok(Poly::from_le_u5string(&string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bch::BchStructure;
+    use crate::checksum32::get_checksums;
+
+    #[test]
+    fn finds_existing_generators() {
+        let checksums = get_checksums();
+        for (name, code_len, distance) in [
+            ("bech32", 1023, 4),
+            ("codex32", 93, 9),
+            ("long-codex32", 1023, 9),
+        ] {
+            let checksum = &checksums[name];
+            let candidates = search(checksum.checksum_len(), code_len, distance).unwrap();
+            assert!(
+                candidates
+                    .iter()
+                    .any(|cand| cand.generator == checksum.generator()),
+                "{name} not found among {} candidates",
+                candidates.len(),
+            );
+        }
+    }
+
+    #[test]
+    fn candidates_are_bch() {
+        let candidates = search(13, 93, 9).unwrap();
+        assert!(!candidates.is_empty());
+        for candidate in &candidates {
+            let modulus = candidate.modulus_string();
+            assert_eq!(modulus.len(), 14);
+            assert!(modulus.ends_with('p'));
+            assert_eq!(parse_modulus(&modulus).unwrap(), candidate.generator);
+            let bch = BchStructure::analyze(&candidate.generator).unwrap();
+            assert!(bch.designed_distance >= 9);
+            assert_eq!(bch.length, 93);
+        }
+        assert!(report(&candidates).starts_with(&format!("{} generators", candidates.len())));
+    }
+
+    #[test]
+    fn gen_list() {
+        let candidate = Candidate {
+            generator: parse_modulus("ja45kap").unwrap(),
+            beta_exponent: 1,
+            first_root: 0,
+        };
+        assert_eq!(candidate.gen_list(), "gen=[18,29,21,20,22,29,1]");
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(search(13, 93, 1).is_err());
+        assert!(search(13, 93, 15).is_err());
+        assert!(search(13, 12, 9).is_err());
+        assert!(search(13, 94, 9).is_err());
+        // 37 does not divide 32^k - 1 for small k
+        assert!(search(13, 37, 9).is_err());
+        // 2^60 - 1 = 32^12 - 1 is far too long to search
+        assert!(search(13, (1 << 60) - 1, 9).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+// Bech32 Code Playground
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+/// Weight Distributions and Undetected Errors
+///
+/// An error pattern goes undetected exactly when it is itself a codeword, so
+/// the number of codewords of each small weight tells us how many patterns of
+/// that many substitutions slip through. We count these exactly, using the
+/// same meet-in-the-middle split as the `distance` module: each codeword, with
+/// its first nonzero coefficient scaled to 1, splits uniquely into a first
+/// part and a last part whose syndromes agree, so we tabulate the last parts
+/// and count the matches which start after each first part.
+///
+/// For error patterns too heavy to count, we instead estimate the rate at
+/// which they go undetected by Monte Carlo sampling under an `ErrorModel`.
+///
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+use crate::base32::u5;
+use crate::checksum32::Checksum;
+use crate::distance::{n_threads, ParityCheck, Rng};
+
+/// A random error model, for Monte Carlo estimation
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ErrorModel {
+    /// Substitutions at the given number of distinct, uniformly random
+    /// positions, each by a uniformly random nonzero value
+    Substitutions(usize),
+    /// Substitutions confined to a window of the given number of consecutive
+    /// positions at a uniformly random location, with the first and last
+    /// positions of the window substituted and the rest uniformly random
+    Burst(usize),
+    /// Two adjacent, unequal characters swapped at a uniformly random location
+    AdjacentSwap,
+}
+
+impl fmt::Display for ErrorModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorModel::Substitutions(1) => f.write_str("1 substitution"),
+            ErrorModel::Substitutions(n) => write!(f, "{n} substitutions"),
+            ErrorModel::Burst(n) => write!(f, "burst of length {n}"),
+            ErrorModel::AdjacentSwap => f.write_str("adjacent swap"),
+        }
+    }
+}
+
+/// A Monte Carlo estimate of an undetected-error rate
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Estimate {
+    /// The number of sampled errors which went undetected
+    pub undetected: u64,
+    /// The number of sampled errors
+    pub trials: u64,
+}
+
+impl Estimate {
+    /// The estimated rate
+    pub fn rate(&self) -> f64 {
+        self.undetected as f64 / self.trials as f64
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} / {} ", self.undetected, self.trials)?;
+        if self.undetected == 0 {
+            // The "rule of three" 95% confidence upper bound
+            write!(f, "(< {:.1e})", 3.0 / self.trials as f64)
+        } else {
+            write!(f, "({:.1e})", self.rate())
+        }
+    }
+}
+
+/// Counts the codewords of weight `weight` for strings of `length`
+/// characters, or returns `None` if this would take more than `work_limit`
+/// lookups
+pub fn count_codewords(
+    checksum: &Checksum,
+    length: usize,
+    weight: usize,
+    work_limit: u64,
+) -> Result<Option<u64>, String> {
+    let matrix = ParityCheck::for_checksum(checksum, length)?;
+    if weight == 0 {
+        return Ok(Some(1));
+    }
+    if weight == 1 {
+        return Ok(Some(0));
+    }
+    let Some((a, b)) = matrix.mitm_split(weight, work_limit) else {
+        return Ok(None);
+    };
+
+    // Map each syndrome of a last part to the first positions of all last
+    // parts with that syndrome, in increasing order
+    let mut table: HashMap<u128, Vec<usize>> = HashMap::new();
+    for first in 0..length {
+        for v in 1..32 {
+            let acc = matrix.scaled[first][v];
+            matrix.for_each_combination(first + 1, b - 1, acc, &mut vec![], &mut |sum, _| {
+                table.entry(sum).or_default().push(first);
+                true
+            });
+        }
+    }
+
+    // Count the last parts which match each first part and start after it
+    let n_threads = n_threads();
+    let count = AtomicU64::new(0);
+    thread::scope(|scope| {
+        for t in 0..n_threads {
+            let (matrix, table, count) = (&matrix, &table, &count);
+            scope.spawn(move || {
+                let mut local = 0;
+                for first in (t..length).step_by(n_threads) {
+                    let mut chosen = vec![(first, u5::ONE)];
+                    let acc = matrix.scaled[first][1];
+                    matrix.for_each_combination(
+                        first + 1,
+                        a - 1,
+                        acc,
+                        &mut chosen,
+                        &mut |sum, chosen| {
+                            let last = chosen.last().unwrap().0;
+                            if let Some(firsts) = table.get(&sum) {
+                                let start = firsts.partition_point(|&f| f <= last);
+                                local += (firsts.len() - start) as u64;
+                            }
+                            true
+                        },
+                    );
+                }
+                count.fetch_add(local, Ordering::Relaxed);
+            });
+        }
+    });
+    // Each codeword was counted once per nonzero scalar multiple
+    Ok(Some(count.into_inner() * 31))
+}
+
+/// Samples an error pattern from the model, as (position, value) pairs
+fn sample(model: ErrorModel, length: usize, rng: &mut Rng) -> Vec<(usize, u5)> {
+    match model {
+        ErrorModel::Substitutions(n) => {
+            let mut positions: Vec<usize> = vec![];
+            while positions.len() < n {
+                let pos = rng.below(length);
+                if !positions.contains(&pos) {
+                    positions.push(pos);
+                }
+            }
+            positions
+                .into_iter()
+                .map(|pos| (pos, u5::from(1 + rng.below(31) as u8)))
+                .collect()
+        }
+        ErrorModel::Burst(n) => {
+            let start = rng.below(length + 1 - n);
+            (0..n)
+                .map(|i| {
+                    let value = if i == 0 || i == n - 1 {
+                        1 + rng.below(31)
+                    } else {
+                        rng.below(32)
+                    };
+                    (start + i, u5::from(value as u8))
+                })
+                .collect()
+        }
+        ErrorModel::AdjacentSwap => {
+            // Swapping a and b adds b - a to one position and a - b to the other
+            let pos = rng.below(length - 1);
+            let diff = u5::from(1 + rng.below(31) as u8);
+            vec![(pos, diff), (pos + 1, diff)]
+        }
+    }
+}
+
+/// Estimates the rate at which errors from the model go undetected, for
+/// strings of `length` characters, using `trials` samples spread across
+/// threads
+pub fn estimate_undetected(
+    checksum: &Checksum,
+    length: usize,
+    model: ErrorModel,
+    trials: u64,
+) -> Result<Estimate, String> {
+    let matrix = ParityCheck::for_checksum(checksum, length)?;
+    match model {
+        ErrorModel::Substitutions(n) | ErrorModel::Burst(n) if n == 0 || n > length => {
+            return Err(format!("{model} does not fit in length {length}"));
+        }
+        _ => {}
+    }
+
+    let n_threads = n_threads();
+    let undetected = AtomicU64::new(0);
+    thread::scope(|scope| {
+        for t in 0..n_threads {
+            let (matrix, undetected) = (&matrix, &undetected);
+            scope.spawn(move || {
+                let mut rng = Rng::for_thread(t);
+                let mut local = 0;
+                for _ in (t as u64..trials).step_by(n_threads) {
+                    let syndrome = sample(model, length, &mut rng)
+                        .into_iter()
+                        .fold(0, |acc, (pos, v)| {
+                            acc ^ matrix.scaled[pos][usize::from(u8::from(v))]
+                        });
+                    if syndrome == 0 {
+                        local += 1;
+                    }
+                }
+                undetected.fetch_add(local, Ordering::Relaxed);
+            });
+        }
+    });
+    Ok(Estimate {
+        undetected: undetected.into_inner(),
+        trials,
+    })
+}
+
+/// Produces a table of the exact numbers of low-weight codewords, and of
+/// estimated undetected-error rates under a selection of error models, for a
+/// checksum on strings of `length` characters
+pub fn report(
+    checksum: &Checksum,
+    length: usize,
+    trials: u64,
+    work_limit: u64,
+) -> Result<String, String> {
+    let designed = match checksum.bch_structure() {
+        Ok(bch) if length as u64 <= bch.length => bch.designed_distance,
+        _ => 2,
+    };
+    let mut ret = format!("length {length}\n    weight  codewords");
+    for weight in designed..designed + 3 {
+        ret.push_str(&format!("\n    {weight:6}  "));
+        match count_codewords(checksum, length, weight, work_limit)? {
+            Some(count) => ret.push_str(&count.to_string()),
+            None => ret.push_str("(too expensive)"),
+        }
+    }
+
+    let n = checksum.checksum_len();
+    let mut models = vec![
+        ErrorModel::Substitutions(designed),
+        ErrorModel::Substitutions(designed + 1),
+        ErrorModel::Substitutions(2 * designed),
+        ErrorModel::Burst(n + 1),
+        ErrorModel::Burst(2 * n),
+        ErrorModel::AdjacentSwap,
+    ];
+    models.retain(|model| match *model {
+        ErrorModel::Substitutions(k) | ErrorModel::Burst(k) => k <= length,
+        ErrorModel::AdjacentSwap => true,
+    });
+    ret.push_str("\n    model                  undetected");
+    for model in models {
+        let estimate = estimate_undetected(checksum, length, model, trials)?;
+        ret.push_str(&format!("\n    {:21}  {estimate}", model.to_string()));
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum32::get_checksums;
+    use crate::checksum32::tests::parity;
+    use crate::poly::Poly;
+
+    #[test]
+    fn brute_force_counts() {
+        // At length 8 there are only 32^2 bech32 codewords, so count them all
+        let checksums = get_checksums();
+        let bech32 = &checksums["bech32"];
+        let generator = bech32.generator();
+        let mut expected = [0u64; 9];
+        for m0 in 0..32 {
+            for m1 in 0..32 {
+                let message = Poly::from_coefficients(vec![u5::from(m0), u5::from(m1)]);
+                let codeword = &message * &generator;
+                let weight = codeword
+                    .coefficients()
+                    .iter()
+                    .filter(|&&c| c != u5::ZERO)
+                    .count();
+                expected[weight] += 1;
+            }
+        }
+        // (the heaviest weights are slow to count in debug builds)
+        for (weight, &expected) in expected.iter().enumerate().take(7) {
+            let count = count_codewords(bech32, 8, weight, u64::MAX).unwrap();
+            assert_eq!(count, Some(expected), "weight {weight}");
+        }
+        assert_eq!(count_codewords(bech32, 8, 4, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn monte_carlo() {
+        let parity = parity();
+        // Two substitutions cancel out exactly when they are equal
+        let estimate =
+            estimate_undetected(&parity, 20, ErrorModel::Substitutions(2), 31_000).unwrap();
+        assert!((800..1200).contains(&estimate.undetected), "{estimate}");
+        // and swaps are never detected
+        let estimate = estimate_undetected(&parity, 20, ErrorModel::AdjacentSwap, 1000).unwrap();
+        assert_eq!(estimate.undetected, 1000);
+        assert_eq!(estimate.to_string(), "1000 / 1000 (1.0e0)");
+
+        // whereas bech32 detects all of these
+        let checksums = get_checksums();
+        let bech32 = &checksums["bech32"];
+        for model in [
+            ErrorModel::Substitutions(3),
+            ErrorModel::Burst(6),
+            ErrorModel::AdjacentSwap,
+        ] {
+            let estimate = estimate_undetected(bech32, 42, model, 1000).unwrap();
+            assert_eq!(estimate.undetected, 0);
+            assert_eq!(estimate.to_string(), "0 / 1000 (< 3.0e-3)");
+        }
+        assert!(estimate_undetected(bech32, 42, ErrorModel::Burst(43), 1000).is_err());
+    }
+
+    #[test]
+    fn table() {
+        let checksums = get_checksums();
+        let table = report(&checksums["bech32"], 10, 100, 1 << 20).unwrap();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines[0], "length 10");
+        assert_eq!(lines[2].split_whitespace().collect::<Vec<_>>(), ["4", "0"]);
+        assert!(lines.iter().any(|line| line.contains("adjacent swap")));
+    }
+}